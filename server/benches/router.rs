@@ -0,0 +1,84 @@
+//! Compares the server's path router with the regex routing it replaced.
+//!
+//! Run with `cargo bench --bench router`.
+
+#![allow(missing_docs)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use openapi_client::server::router::{self, PathRouter};
+use openapi_client::BASE_PATH;
+use regex::{Regex, RegexSet};
+
+const PATHS: &[&str] = &[
+    "/api/v3/pet",
+    "/api/v3/pet/findByStatus",
+    "/api/v3/pet/12345",
+    "/api/v3/store/order/789",
+    "/api/v3/user/logout",
+    "/api/v3/user/some%20user",
+    "/api/v3/unknown/path",
+];
+
+/// The distinct path templates in the route table, with templates that have
+/// parameters last so that the regex routing prefers static segments too.
+fn templates() -> Vec<&'static str> {
+    let mut templates = router::templates();
+    templates.sort_by_key(|template| template.contains('{'));
+    templates
+}
+
+/// Routing as it was done with regexes: a `RegexSet` to find the matching template,
+/// then a per-template `Regex` to capture its parameters.
+struct RegexRouter {
+    set: RegexSet,
+    captures: Vec<Regex>,
+}
+
+impl RegexRouter {
+    fn new(templates: &[&str]) -> Self {
+        let patterns = templates.iter().map(|template| {
+            let segments = template.split('/').map(|segment| {
+                match segment.strip_prefix('{').and_then(|segment| segment.strip_suffix('}')) {
+                    Some(name) => format!("(?P<{}>[^/?#]*)", name),
+                    None => regex::escape(segment),
+                }
+            });
+            format!("^{}{}$", regex::escape(BASE_PATH), segments.collect::<Vec<_>>().join("/"))
+        }).collect::<Vec<_>>();
+
+        RegexRouter {
+            set: RegexSet::new(&patterns).expect("Unable to create regex set"),
+            captures: patterns.iter().map(|pattern| Regex::new(pattern).expect("Unable to create regex")).collect(),
+        }
+    }
+
+    fn matches<'a>(&self, path: &'a str) -> Option<(usize, Vec<&'a str>)> {
+        // As before, the first matching template wins
+        let id = self.set.matches(path).into_iter().next()?;
+        let captures = self.captures[id].captures(path)?;
+        Some((id, captures.iter().skip(1).flatten().map(|capture| capture.as_str()).collect()))
+    }
+}
+
+fn routing(c: &mut Criterion) {
+    let templates = templates();
+    let path_router = PathRouter::new(BASE_PATH, &templates);
+    let regex_router = RegexRouter::new(&templates);
+
+    let mut group = c.benchmark_group("routing");
+    group.bench_function("path router", |b| b.iter(|| {
+        for path in PATHS {
+            let path = path_router.matches(black_box(path));
+            black_box((path.template(), path.param("petId")));
+        }
+    }));
+    group.bench_function("regex", |b| b.iter(|| {
+        for path in PATHS {
+            black_box(regex_router.matches(black_box(path)));
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, routing);
+criterion_main!(benches);
@@ -25,6 +25,9 @@ use openapi_client::{Api, ApiNoContext, Client, ContextWrapperExt, models,
                       UpdateUserResponse,
                      };
 use clap::{App, Arg};
+use openapi_client::client::{ClientCredentials, RefreshToken, TokenEndpoint, TokenProvider};
+use openapi_client::media::MediaType;
+use std::sync::Arc;
 
 #[allow(unused_imports)]
 use log::info;
@@ -76,6 +79,43 @@ fn main() {
             .takes_value(true)
             .default_value("8080")
             .help("Port to contact"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&["json", "xml", "form"])
+            .default_value("json")
+            .help("Media type to encode request bodies with"))
+        .arg(Arg::with_name("base-path")
+            .long("base-path")
+            .takes_value(true)
+            .default_value(openapi_client::BASE_PATH)
+            .help("Path that the API is mounted at on the server"))
+        .arg(Arg::with_name("api-key")
+            .long("api-key")
+            .takes_value(true)
+            .conflicts_with("bearer")
+            .help("API key to send to operations that accept one"))
+        .arg(Arg::with_name("bearer")
+            .long("bearer")
+            .takes_value(true)
+            .help("Bearer token to send to operations that accept one"))
+        .arg(Arg::with_name("token-url")
+            .long("token-url")
+            .takes_value(true)
+            .requires("client-id")
+            .help("OAuth2 token endpoint to fetch bearer tokens from"))
+        .arg(Arg::with_name("client-id")
+            .long("client-id")
+            .takes_value(true)
+            .help("OAuth2 client ID"))
+        .arg(Arg::with_name("client-secret")
+            .long("client-secret")
+            .takes_value(true)
+            .help("OAuth2 client secret, for a confidential client"))
+        .arg(Arg::with_name("refresh-token")
+            .long("refresh-token")
+            .takes_value(true)
+            .help("OAuth2 refresh token, used instead of the client credentials grant"))
         .get_matches();
 
     let is_https = matches.is_present("https");
@@ -84,19 +124,58 @@ fn main() {
                            matches.value_of("host").unwrap(),
                            matches.value_of("port").unwrap());
 
+    let base_path = matches.value_of("base-path").unwrap();
+
+    let request_media_type = match matches.value_of("format") {
+        Some("xml") => MediaType::Xml,
+        Some("form") => MediaType::FormUrlEncoded,
+        _ => MediaType::Json,
+    };
+
+    let auth_data = match (matches.value_of("api-key"), matches.value_of("bearer")) {
+        (Some(api_key), _) => Some(AuthData::ApiKey(api_key.to_string())),
+        (_, Some(token)) => Some(AuthData::bearer(token)),
+        _ => None,
+    };
+
+    let token_provider: Option<Arc<dyn TokenProvider>> = matches.value_of("token-url").map(|token_url| {
+        let mut endpoint = TokenEndpoint::new(token_url, matches.value_of("client-id").unwrap())
+            .expect("Invalid token endpoint");
+        if let Some(client_secret) = matches.value_of("client-secret") {
+            endpoint = endpoint.with_client_secret(client_secret);
+        }
+        let token_provider: Arc<dyn TokenProvider> = match matches.value_of("refresh-token") {
+            Some(refresh_token) => Arc::new(RefreshToken::new(endpoint, refresh_token)),
+            None => Arc::new(ClientCredentials::new(endpoint)),
+        };
+        token_provider
+    });
+
     let context: ClientContext =
-        swagger::make_context!(ContextBuilder, EmptyContext, None as Option<AuthData>, XSpanIdString::default());
+        swagger::make_context!(ContextBuilder, EmptyContext, auth_data, XSpanIdString::default());
 
     let mut client : Box<dyn ApiNoContext<ClientContext>> = if matches.is_present("https") {
         // Using Simple HTTPS
         let client = Box::new(Client::try_new_https(&base_url)
-            .expect("Failed to create HTTPS client"));
+            .expect("Failed to create HTTPS client")
+            .with_request_media_type(request_media_type)
+            .with_base_path(base_path));
+        let client = match token_provider {
+            Some(token_provider) => Box::new(client.with_token_provider(token_provider)),
+            None => client,
+        };
         Box::new(client.with_context(context))
     } else {
         // Using HTTP
         let client = Box::new(Client::try_new_http(
             &base_url)
-            .expect("Failed to create HTTP client"));
+            .expect("Failed to create HTTP client")
+            .with_request_media_type(request_media_type)
+            .with_base_path(base_path));
+        let client = match token_provider {
+            Some(token_provider) => Box::new(client.with_token_provider(token_provider)),
+            None => client,
+        };
         Box::new(client.with_context(context))
     };
 
@@ -120,7 +199,7 @@ fn main() {
         },
         Some("FindPetsByStatus") => {
             let result = rt.block_on(client.find_pets_by_status(
-                  Some("status_example".to_string())
+                  Some(models::PetStatus::Available)
             ));
             info!("{:?} (X-Span-ID: {:?})", result, (client.context() as &dyn Has<XSpanIdString>).get().clone());
         },
@@ -17,9 +17,14 @@ async fn main() {
         .arg(Arg::with_name("https")
             .long("https")
             .help("Whether to use HTTPS or not"))
+        .arg(Arg::with_name("base-path")
+            .long("base-path")
+            .takes_value(true)
+            .default_value(openapi_client::BASE_PATH)
+            .help("Path to serve the API at"))
         .get_matches();
 
     let addr = "127.0.0.1:8080";
 
-    server::create(addr, matches.is_present("https")).await;
+    server::create(addr, matches.is_present("https"), matches.value_of("base-path").unwrap()).await;
 }
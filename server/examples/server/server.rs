@@ -12,8 +12,7 @@ use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
-use swagger::{Has, XSpanIdString};
-use swagger::auth::MakeAllowAllAuthenticator;
+use swagger::{AuthData, Has, XSpanIdString};
 use swagger::EmptyContext;
 use tokio::net::TcpListener;
 
@@ -26,14 +25,21 @@ use openapi_client::models;
 use openapi_client::cors::MakeAddAccessControlOrigin;
 
 /// Builds an SSL implementation for Simple HTTPS from some hard-coded file names
-pub async fn create(addr: &str, https: bool) {
+pub async fn create(addr: &str, https: bool, base_path: &str) {
     let addr = addr.parse().expect("Failed to parse bind address");
 
-    let server = Server::new();
+    // Sessions started by loginUser, whose tokens are accepted as API keys and bearer tokens
+    let sessions = Sessions::new(InMemorySessionStore::new());
+    let server = Server::new(sessions.clone());
 
-    let service = MakeService::new(server);
+    let service = MakeService::new(server)
+        .with_base_path(base_path);
 
-    let service = MakeAllowAllAuthenticator::new(service, "cosmo");
+    // Authenticate the api_key header against the keys in the example key file
+    let keys = FileKeyStore::open("examples/api-keys.json").expect("Failed to load API keys");
+    let service = MakeAuthenticator::new(service, keys)
+        .with_keys(sessions.clone())
+        .with_bearer(sessions);
 
     let service = MakeAddAccessControlOrigin::new(service);
 
@@ -85,14 +91,15 @@ pub async fn create(addr: &str, https: bool) {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Server<C> {
     marker: PhantomData<C>,
+    sessions: Sessions,
 }
 
 impl<C> Server<C> {
-    pub fn new() -> Self {
-        Server{marker: PhantomData}
+    pub fn new(sessions: Sessions) -> Self {
+        Server{marker: PhantomData, sessions}
     }
 }
 
@@ -118,12 +125,12 @@ use openapi_client::{
     LogoutUserResponse,
     UpdateUserResponse,
 };
-use openapi_client::server::MakeService;
+use openapi_client::server::{FileKeyStore, InMemorySessionStore, MakeAuthenticator, MakeService, Sessions};
 use std::error::Error;
 use swagger::ApiError;
 
 #[async_trait]
-impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
+impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Has<Option<AuthData>> + Clone + Send + Sync
 {
     /// Add a new pet to the store
     async fn add_pet(
@@ -132,7 +139,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<AddPetResponse, ApiError>
     {
         let context = context.clone();
-        info!("add_pet({:?}) - X-Span-ID: {:?}", pet, context.get().0.clone());
+        info!("add_pet({:?}) - X-Span-ID: {:?}", pet, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -144,18 +151,18 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<DeletePetResponse, ApiError>
     {
         let context = context.clone();
-        info!("delete_pet({}, {:?}) - X-Span-ID: {:?}", pet_id, api_key, context.get().0.clone());
+        info!("delete_pet({}, {:?}) - X-Span-ID: {:?}", pet_id, api_key, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
     /// Finds Pets by status
     async fn find_pets_by_status(
         &self,
-        status: Option<String>,
+        status: Option<models::PetStatus>,
         context: &C) -> Result<FindPetsByStatusResponse, ApiError>
     {
         let context = context.clone();
-        info!("find_pets_by_status({:?}) - X-Span-ID: {:?}", status, context.get().0.clone());
+        info!("find_pets_by_status({:?}) - X-Span-ID: {:?}", status, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -166,7 +173,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<FindPetsByTagsResponse, ApiError>
     {
         let context = context.clone();
-        info!("find_pets_by_tags({:?}) - X-Span-ID: {:?}", tags, context.get().0.clone());
+        info!("find_pets_by_tags({:?}) - X-Span-ID: {:?}", tags, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -177,7 +184,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<GetPetByIdResponse, ApiError>
     {
         let context = context.clone();
-        info!("get_pet_by_id({}) - X-Span-ID: {:?}", pet_id, context.get().0.clone());
+        info!("get_pet_by_id({}) - X-Span-ID: {:?}", pet_id, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -188,7 +195,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<UpdatePetResponse, ApiError>
     {
         let context = context.clone();
-        info!("update_pet({:?}) - X-Span-ID: {:?}", pet, context.get().0.clone());
+        info!("update_pet({:?}) - X-Span-ID: {:?}", pet, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -201,7 +208,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<UpdatePetWithFormResponse, ApiError>
     {
         let context = context.clone();
-        info!("update_pet_with_form({}, {:?}, {:?}) - X-Span-ID: {:?}", pet_id, name, status, context.get().0.clone());
+        info!("update_pet_with_form({}, {:?}, {:?}) - X-Span-ID: {:?}", pet_id, name, status, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -212,7 +219,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<DeleteOrderResponse, ApiError>
     {
         let context = context.clone();
-        info!("delete_order({}) - X-Span-ID: {:?}", order_id, context.get().0.clone());
+        info!("delete_order({}) - X-Span-ID: {:?}", order_id, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -222,7 +229,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<GetInventoryResponse, ApiError>
     {
         let context = context.clone();
-        info!("get_inventory() - X-Span-ID: {:?}", context.get().0.clone());
+        info!("get_inventory() - X-Span-ID: {:?}", Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -233,7 +240,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<GetOrderByIdResponse, ApiError>
     {
         let context = context.clone();
-        info!("get_order_by_id({}) - X-Span-ID: {:?}", order_id, context.get().0.clone());
+        info!("get_order_by_id({}) - X-Span-ID: {:?}", order_id, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -244,7 +251,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<PlaceOrderResponse, ApiError>
     {
         let context = context.clone();
-        info!("place_order({:?}) - X-Span-ID: {:?}", order, context.get().0.clone());
+        info!("place_order({:?}) - X-Span-ID: {:?}", order, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -255,7 +262,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<CreateUserResponse, ApiError>
     {
         let context = context.clone();
-        info!("create_user({:?}) - X-Span-ID: {:?}", user, context.get().0.clone());
+        info!("create_user({:?}) - X-Span-ID: {:?}", user, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -266,7 +273,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<CreateUsersWithListInputResponse, ApiError>
     {
         let context = context.clone();
-        info!("create_users_with_list_input({:?}) - X-Span-ID: {:?}", user, context.get().0.clone());
+        info!("create_users_with_list_input({:?}) - X-Span-ID: {:?}", user, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -277,7 +284,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<DeleteUserResponse, ApiError>
     {
         let context = context.clone();
-        info!("delete_user(\"{}\") - X-Span-ID: {:?}", username, context.get().0.clone());
+        info!("delete_user(\"{}\") - X-Span-ID: {:?}", username, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -288,7 +295,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<GetUserByNameResponse, ApiError>
     {
         let context = context.clone();
-        info!("get_user_by_name(\"{}\") - X-Span-ID: {:?}", username, context.get().0.clone());
+        info!("get_user_by_name(\"{}\") - X-Span-ID: {:?}", username, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
@@ -300,8 +307,19 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<LoginUserResponse, ApiError>
     {
         let context = context.clone();
-        info!("login_user({:?}, {:?}) - X-Span-ID: {:?}", username, password, context.get().0.clone());
-        Err(ApiError("Generic failure".into()))
+        info!("login_user({:?}) - X-Span-ID: {:?}", username, Has::<XSpanIdString>::get(&context).0.clone());
+        let username = match (username, password) {
+            (Some(username), Some(password)) if !username.is_empty() && !password.is_empty() => username,
+            _ => return Ok(LoginUserResponse::InvalidUsername),
+        };
+        // There are no user accounts in this example, so any password is accepted
+        let session = self.sessions.login(username, vec!["read:pets", "write:pets"])
+            .map_err(|e| ApiError(format!("Unable to start session: {}", e)))?;
+        Ok(LoginUserResponse::SuccessfulOperation {
+            body: session.token,
+            x_rate_limit: Some(5000),
+            x_expires_after: Some(session.expires_at),
+        })
     }
 
     /// Logs out current logged in user session
@@ -310,8 +328,10 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<LogoutUserResponse, ApiError>
     {
         let context = context.clone();
-        info!("logout_user() - X-Span-ID: {:?}", context.get().0.clone());
-        Err(ApiError("Generic failure".into()))
+        info!("logout_user() - X-Span-ID: {:?}", Has::<XSpanIdString>::get(&context).0.clone());
+        self.sessions.logout(Has::<Option<AuthData>>::get(&context))
+            .map_err(|e| ApiError(format!("Unable to end session: {}", e)))?;
+        Ok(LogoutUserResponse::SuccessfulOperation { status: 200 })
     }
 
     /// Update user
@@ -322,7 +342,7 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         context: &C) -> Result<UpdateUserResponse, ApiError>
     {
         let context = context.clone();
-        info!("update_user(\"{}\", {:?}) - X-Span-ID: {:?}", username, user, context.get().0.clone());
+        info!("update_user(\"{}\", {:?}) - X-Span-ID: {:?}", username, user, Has::<XSpanIdString>::get(&context).0.clone());
         Err(ApiError("Generic failure".into()))
     }
 
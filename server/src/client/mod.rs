@@ -1,7 +1,9 @@
 use async_trait::async_trait;
 use futures::{Stream, future, future::BoxFuture, stream, future::TryFutureExt, future::FutureExt, stream::StreamExt};
-use hyper::header::{HeaderName, HeaderValue, CONTENT_TYPE};
-use hyper::{Body, Request, Response, service::Service, Uri};
+use hyper::header::{HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use hyper::HeaderMap;
+use hyper::{Body, Request, Response, StatusCode, service::Service, Uri};
+use log::warn;
 use percent_encoding::{utf8_percent_encode, AsciiSet};
 use std::borrow::Cow;
 use std::convert::TryInto;
@@ -16,12 +18,19 @@ use std::str;
 use std::str::FromStr;
 use std::string::ToString;
 use std::task::{Context, Poll};
+use serde::de::DeserializeOwned;
 use swagger::{ApiError, AuthData, BodyExt, Connector, DropContextService, Has, XSpanIdString};
-use url::form_urlencoded;
 
 
 use crate::models;
 use crate::header;
+use crate::media::MediaType;
+use crate::problem::{Problem, PROBLEM_JSON};
+use crate::query::{QuerySerializer, Style};
+
+pub mod oauth;
+
+pub use oauth::{ClientCredentials, RefreshToken, StaticToken, TokenEndpoint, TokenProvider};
 
 /// https://url.spec.whatwg.org/#fragment-percent-encode-set
 #[allow(dead_code)]
@@ -56,6 +65,111 @@ use crate::{Api,
      UpdateUserResponse
      };
 
+/// The media type a response body has been encoded with. Bodies without a recognised
+/// `Content-Type` are assumed to be JSON.
+fn response_media_type(headers: &HeaderMap) -> MediaType {
+    headers.get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse().ok())
+        .unwrap_or(MediaType::Json)
+}
+
+/// Deserialize a response body encoded as `media_type`.
+fn deserialize_response<T: DeserializeOwned>(media_type: MediaType, body: &str) -> Result<T, ApiError> {
+    match media_type {
+        MediaType::Xml => serde_xml_rs::from_str::<T>(body)
+            .map_err(|e| ApiError(format!("Response body did not match the schema: {}", e))),
+        MediaType::FormUrlEncoded => crate::form::from_bytes::<T>(body.as_bytes())
+            .map_err(|e| ApiError(format!("Response body did not match the schema: {}", e))),
+        MediaType::Json => serde_json::from_str::<T>(body)
+            .map_err(|e| ApiError(format!("Response body did not match the schema: {}", e))),
+    }
+}
+
+/// Whether a response carries an RFC 7807 problem details body.
+fn is_problem(headers: &HeaderMap) -> bool {
+    headers.get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+        .map_or(false, |content_type| content_type.essence_str() == PROBLEM_JSON)
+}
+
+/// Read a problem details body, which the client returns as the operation's `Problem`
+/// response.
+async fn read_problem(response: Response<Body>) -> Result<Problem, ApiError> {
+    let body = response.into_body().into_raw().await
+        .map_err(|e| ApiError(format!("Failed to read response: {}", e)))?;
+    serde_json::from_slice::<Problem>(&body)
+        .map_err(|e| ApiError(format!("Problem details did not match the schema: {}", e)))
+}
+
+/// XML representation of a list of pets, as returned by the `findPetsBy*` operations.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename = "pets")]
+struct XmlPets {
+    #[serde(rename = "pet", default)]
+    pets: Vec<models::Pet>,
+}
+
+/// Deserialize a list of pets, which XML wraps in a `<pets>` element.
+fn deserialize_pets(media_type: MediaType, body: &str) -> Result<Vec<models::Pet>, ApiError> {
+    match media_type {
+        MediaType::Xml => deserialize_response::<XmlPets>(media_type, body).map(|pets| pets.pets),
+        _ => deserialize_response(media_type, body),
+    }
+}
+
+/// How an operation's declared security schemes carry credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SecurityScheme {
+    /// An API key, sent in the named header
+    ApiKey(&'static str),
+    /// An OAuth2 or HTTP `bearer` token, sent as `Authorization: Bearer`
+    Bearer,
+    /// HTTP `basic` credentials, sent as `Authorization: Basic`. No operation in this
+    /// API declares such a scheme, so the only use is `oauth::TokenEndpoint`
+    /// authenticating the client to its token endpoint.
+    Basic,
+}
+
+/// Add the context's credentials to a request, in the way that the operation's security
+/// schemes expect. Credentials of a kind that the operation doesn't declare, including
+/// any credentials for an operation that declares no schemes, aren't sent, with a warning.
+fn add_credentials(request: &mut Request<Body>, auth_data: Option<&AuthData>, schemes: &[SecurityScheme]) -> Result<(), ApiError> {
+    let auth_data = match auth_data {
+        Some(auth_data) => auth_data,
+        None => return Ok(()),
+    };
+    let credentials = match auth_data {
+        AuthData::ApiKey(api_key) => schemes.iter().find_map(|scheme| match scheme {
+            SecurityScheme::ApiKey(header) => Some((HeaderName::from_static(header), api_key.clone())),
+            _ => None,
+        }),
+        AuthData::Bearer(bearer) if schemes.contains(&SecurityScheme::Bearer) =>
+            Some((AUTHORIZATION, swagger::auth::Header(bearer.clone()).to_string())),
+        AuthData::Basic(basic) if schemes.contains(&SecurityScheme::Basic) =>
+            Some((AUTHORIZATION, swagger::auth::Header(basic.clone()).to_string())),
+        _ => None,
+    };
+    let (name, value) = match credentials {
+        Some(credentials) => credentials,
+        None => {
+            let kind = match auth_data {
+                AuthData::ApiKey(_) => "an API key",
+                AuthData::Bearer(_) => "a bearer token",
+                AuthData::Basic(_) => "Basic credentials",
+            };
+            warn!("Not sending {} to {} {}, which declares no security scheme for it",
+                kind, request.method(), request.uri().path());
+            return Ok(());
+        },
+    };
+    let value = HeaderValue::from_str(&value)
+        .map_err(|e| ApiError(format!("Unable to create {} header: {}", name, e)))?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
+
 /// Convert input into a base path, e.g. "http://example:123". Also checks the scheme as it goes.
 fn into_base_path(input: impl TryInto<Uri, Error=hyper::http::uri::InvalidUri>, correct_scheme: Option<&'static str>) -> Result<String, ClientInitError> {
     // First convert to Uri, since a base path is a subset of Uri.
@@ -90,6 +204,15 @@ pub struct Client<S, C> where
     /// Base path of the API
     base_path: String,
 
+    /// Path that the API is mounted at on the server
+    api_base_path: String,
+
+    /// Media type used to encode request bodies
+    request_media_type: MediaType,
+
+    /// Source of bearer tokens for requests whose context has no credentials
+    token_provider: Option<Arc<dyn TokenProvider>>,
+
     /// Marker
     marker: PhantomData<fn(C)>,
 }
@@ -103,7 +226,7 @@ impl<S, C> fmt::Debug for Client<S, C> where
     C: Clone + Send + Sync + 'static
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Client {{ base_path: {} }}", self.base_path)
+        write!(f, "Client {{ base_path: {}{} }}", self.base_path, self.api_base_path)
     }
 }
 
@@ -119,6 +242,9 @@ impl<S, C> Clone for Client<S, C> where
         Self {
             client_service: self.client_service.clone(),
             base_path: self.base_path.clone(),
+            api_base_path: self.api_base_path.clone(),
+            request_media_type: self.request_media_type,
+            token_provider: self.token_provider.clone(),
             marker: PhantomData,
         }
     }
@@ -154,6 +280,9 @@ impl<Connector, C> Client<DropContextService<hyper::client::Client<Connector, Bo
         Ok(Self {
             client_service,
             base_path: into_base_path(base_path, protocol)?,
+            api_base_path: crate::BASE_PATH.to_string(),
+            request_media_type: MediaType::Json,
+            token_provider: None,
             marker: PhantomData,
         })
     }
@@ -165,6 +294,26 @@ pub enum HyperClient {
     Https(hyper::client::Client<HttpsConnector, Body>),
 }
 
+impl HyperClient {
+    /// Create a client for URLs with the given scheme, `http` or `https`.
+    fn try_new(scheme: &str) -> Result<Self, ClientInitError> {
+        let connector = Connector::builder();
+
+        match scheme {
+            "http" => {
+                Ok(HyperClient::Http(hyper::client::Client::builder().build(connector.build())))
+            },
+            "https" => {
+                let connector = connector.https()
+                   .build()
+                   .map_err(ClientInitError::SslError)?;
+                Ok(HyperClient::Https(hyper::client::Client::builder().build(connector)))
+            },
+            _ => Err(ClientInitError::InvalidScheme),
+        }
+    }
+}
+
 impl Service<Request<Body>> for HyperClient {
     type Response = Response<Body>;
     type Error = hyper::Error;
@@ -200,28 +349,15 @@ impl<C> Client<DropContextService<HyperClient, C>, C> where
         let scheme = uri.scheme_str().ok_or(ClientInitError::InvalidScheme)?;
         let scheme = scheme.to_ascii_lowercase();
 
-        let connector = Connector::builder();
-
-        let client_service = match scheme.as_str() {
-            "http" => {
-                HyperClient::Http(hyper::client::Client::builder().build(connector.build()))
-            },
-            "https" => {
-                let connector = connector.https()
-                   .build()
-                   .map_err(ClientInitError::SslError)?;
-                HyperClient::Https(hyper::client::Client::builder().build(connector))
-            },
-            _ => {
-                return Err(ClientInitError::InvalidScheme);
-            }
-        };
-
+        let client_service = HyperClient::try_new(&scheme)?;
         let client_service = DropContextService::new(client_service);
 
         Ok(Self {
             client_service,
             base_path: into_base_path(base_path, None)?,
+            api_base_path: crate::BASE_PATH.to_string(),
+            request_media_type: MediaType::Json,
+            token_provider: None,
             marker: PhantomData,
         })
     }
@@ -335,9 +471,113 @@ impl<S, C> Client<S, C> where
         Ok(Self {
             client_service,
             base_path: into_base_path(base_path, None)?,
+            api_base_path: crate::BASE_PATH.to_string(),
+            request_media_type: MediaType::Json,
+            token_provider: None,
             marker: PhantomData,
         })
     }
+
+    /// Send requests to the API mounted at `base_path` on the server, e.g. `/petstore/v3`,
+    /// rather than at `BASE_PATH`.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.api_base_path = crate::normalize_base_path(base_path);
+        self
+    }
+
+    /// Encode request bodies as `media_type`, for the operations that accept it.
+    ///
+    /// Request bodies are JSON by default. Operations that don't accept `media_type`
+    /// keep sending JSON. Responses are requested in `media_type` where the operation
+    /// offers it.
+    pub fn with_request_media_type(mut self, media_type: MediaType) -> Self {
+        self.request_media_type = media_type;
+        self
+    }
+
+    /// Fetch bearer tokens from `token_provider`, e.g. an OAuth2 `ClientCredentials`
+    /// grant, for operations that accept them.
+    ///
+    /// A token is only sent when the context carries no credentials, so an `AuthData`
+    /// in the context still takes precedence. If the server rejects the token with a
+    /// 401, it's discarded and the request is sent once more with a new one.
+    pub fn with_token_provider(mut self, token_provider: impl TokenProvider + 'static) -> Self {
+        self.token_provider = Some(Arc::new(token_provider));
+        self
+    }
+
+    /// Build an `Accept` header for an operation producing `produced`, preferring the
+    /// media type that request bodies are encoded with.
+    fn accept_header(&self, produced: &[MediaType]) -> String {
+        let preferred = if produced.contains(&self.request_media_type) {
+            Some(self.request_media_type)
+        } else {
+            produced.first().copied()
+        };
+
+        produced.iter()
+            .map(|media_type| if Some(*media_type) == preferred {
+                media_type.as_str().to_string()
+            } else {
+                format!("{};q=0.9", media_type)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl<S, C> Client<S, C> where
+    S: Service<
+       (Request<Body>, C),
+       Response=Response<Body>> + Clone + Sync + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<crate::ServiceError> + fmt::Display,
+    C: Has<Option<AuthData>> + Clone + Send + Sync + 'static,
+{
+    /// Send a request for an operation with the given security schemes, adding the
+    /// context's credentials or else a token from the token provider.
+    async fn send(&self, mut request: Request<Body>, context: &C, schemes: &[SecurityScheme]) -> Result<Response<Body>, ApiError> {
+        let auth_data = Has::<Option<AuthData>>::get(context).as_ref();
+        let token_provider = match self.token_provider {
+            Some(ref token_provider) if auth_data.is_none() && schemes.contains(&SecurityScheme::Bearer) => token_provider,
+            _ => {
+                add_credentials(&mut request, auth_data, schemes)?;
+                return self.client_service.clone().call((request, context.clone()))
+                    .map_err(|e| ApiError(format!("No response received: {}", e))).await;
+            }
+        };
+
+        // Keep the body, in case the request has to be sent again
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await
+            .map_err(|e| ApiError(format!("Unable to read request body: {}", e)))?;
+
+        let token = token_provider.token().await?;
+        let response = self.send_with_token(&parts, body.clone(), &token, context).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        token_provider.invalidate(&token).await;
+        let new_token = token_provider.token().await?;
+        if new_token == token {
+            // The provider can't do any better, so the 401 stands
+            return Ok(response);
+        }
+        self.send_with_token(&parts, body, &new_token, context).await
+    }
+
+    async fn send_with_token(&self, parts: &hyper::http::request::Parts, body: hyper::body::Bytes, token: &str, context: &C) -> Result<Response<Body>, ApiError> {
+        let mut request = Request::new(Body::from(body));
+        *request.method_mut() = parts.method.clone();
+        *request.uri_mut() = parts.uri.clone();
+        *request.version_mut() = parts.version;
+        *request.headers_mut() = parts.headers.clone();
+        add_credentials(&mut request, Some(&AuthData::bearer(token)), &[SecurityScheme::Bearer])?;
+
+        self.client_service.clone().call((request, context.clone()))
+            .map_err(|e| ApiError(format!("No response received: {}", e))).await
+    }
 }
 
 /// Error type failing to create a Client
@@ -402,15 +642,14 @@ impl<S, C> Api<C> for Client<S, C> where
         param_pet: models::Pet,
         context: &C) -> Result<AddPetResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet",
-            self.base_path
+            "{}{}/pet",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -432,53 +671,51 @@ impl<S, C> Api<C> for Client<S, C> where
         };
 
         // Body parameter
-        let body = param_pet.as_xml();
+        let body = match self.request_media_type {
+            MediaType::Json => serde_json::to_string(&param_pet).expect("impossible to fail to serialize"),
+            MediaType::Xml => param_pet.as_xml(),
+            MediaType::FormUrlEncoded => param_pet.as_form(),
+        };
                 *request.body_mut() = Body::from(body);
 
-        let header = "application/json";
+        let header = self.request_media_type.as_str();
         request.headers_mut().insert(CONTENT_TYPE, match HeaderValue::from_str(header) {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
         });
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(AddPetResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::Pet>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::Pet>(media_type, body)?;
                 Ok(AddPetResponse::SuccessfulOperation
                     (body)
                 )
@@ -514,16 +751,15 @@ impl<S, C> Api<C> for Client<S, C> where
         param_api_key: Option<String>,
         context: &C) -> Result<DeletePetResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet/{pet_id}",
-            self.base_path
+            "{}{}/pet/{pet_id}",
+            self.base_path, self.api_base_path
             ,pet_id=utf8_percent_encode(&param_pet_id.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -550,28 +786,8 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
-
         // Header parameters
-        match param_api_key {
-            Some(param_api_key) => {
+        if let Some(param_api_key) = param_api_key {
         request.headers_mut().append(
             HeaderName::from_static("api_key"),
             #[allow(clippy::redundant_clone)]
@@ -582,14 +798,23 @@ impl<S, C> Api<C> for Client<S, C> where
                         "Invalid header api_key - {}", e)));
                 },
             });
-            },
-            None => {}
         }
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
+
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(DeletePetResponse::Problem);
+        }
 
         match response.status().as_u16() {
+            204 => {
+                Ok(
+                    DeletePetResponse::PetDeleted
+                )
+            }
             400 => {
                 Ok(
                     DeletePetResponse::InvalidPetValue
@@ -617,21 +842,20 @@ impl<S, C> Api<C> for Client<S, C> where
 
     async fn find_pets_by_status(
         &self,
-        param_status: Option<String>,
+        param_status: Option<models::PetStatus>,
         context: &C) -> Result<FindPetsByStatusResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet/findByStatus",
-            self.base_path
+            "{}{}/pet/findByStatus",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             if let Some(param_status) = param_status {
                 query_string.append_pair("status",
-                    &param_status);
+                    &param_status.to_string());
             }
             query_string.finish()
         };
@@ -653,45 +877,39 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(FindPetsByStatusResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<Vec<models::Pet>>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_pets(media_type, body)?;
                 Ok(FindPetsByStatusResponse::SuccessfulOperation
                     (body)
                 )
@@ -726,18 +944,16 @@ impl<S, C> Api<C> for Client<S, C> where
         param_tags: Option<&Vec<String>>,
         context: &C) -> Result<FindPetsByTagsResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet/findByTags",
-            self.base_path
+            "{}{}/pet/findByTags",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             if let Some(param_tags) = param_tags {
-                query_string.append_pair("tags",
-                    &param_tags.iter().map(ToString::to_string).collect::<Vec<String>>().join(","));
+                query_string.append_array("tags", param_tags, Style::Form, true);
             }
             query_string.finish()
         };
@@ -759,45 +975,39 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(FindPetsByTagsResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<Vec<models::Pet>>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_pets(media_type, body)?;
                 Ok(FindPetsByTagsResponse::SuccessfulOperation
                     (body)
                 )
@@ -832,16 +1042,15 @@ impl<S, C> Api<C> for Client<S, C> where
         param_pet_id: i64,
         context: &C) -> Result<GetPetByIdResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet/{pet_id}",
-            self.base_path
+            "{}{}/pet/{pet_id}",
+            self.base_path, self.api_base_path
             ,pet_id=utf8_percent_encode(&param_pet_id.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -862,45 +1071,40 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: api_key, petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::ApiKey("api_key"),
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(GetPetByIdResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::Pet>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::Pet>(media_type, body)?;
                 Ok(GetPetByIdResponse::SuccessfulOperation
                     (body)
                 )
@@ -940,15 +1144,14 @@ impl<S, C> Api<C> for Client<S, C> where
         param_pet: models::Pet,
         context: &C) -> Result<UpdatePetResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet",
-            self.base_path
+            "{}{}/pet",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -969,53 +1172,51 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
-        let body = param_pet.as_xml();
+        let body = match self.request_media_type {
+            MediaType::Json => serde_json::to_string(&param_pet).expect("impossible to fail to serialize"),
+            MediaType::Xml => param_pet.as_xml(),
+            MediaType::FormUrlEncoded => param_pet.as_form(),
+        };
                 *request.body_mut() = Body::from(body);
 
-        let header = "application/json";
+        let header = self.request_media_type.as_str();
         request.headers_mut().insert(CONTENT_TYPE, match HeaderValue::from_str(header) {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
         });
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(UpdatePetResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::Pet>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::Pet>(media_type, body)?;
                 Ok(UpdatePetResponse::SuccessfulOperation
                     (body)
                 )
@@ -1062,16 +1263,15 @@ impl<S, C> Api<C> for Client<S, C> where
         param_status: Option<String>,
         context: &C) -> Result<UpdatePetWithFormResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/pet/{pet_id}",
-            self.base_path
+            "{}{}/pet/{pet_id}",
+            self.base_path, self.api_base_path
             ,pet_id=utf8_percent_encode(&param_pet_id.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             if let Some(param_name) = param_name {
                 query_string.append_pair("name",
                     &param_name);
@@ -1106,29 +1306,21 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                &AuthData::Bearer(ref bearer_header) => {
-                    let auth = swagger::auth::Header(bearer_header.clone());
-                    let header = match HeaderValue::from_str(&format!("{}", auth)) {
-                        Ok(h) => h,
-                        Err(e) => return Err(ApiError(format!("Unable to create Authorization header: {}", e)))
-                    };
-                    request.headers_mut().insert(
-                        hyper::header::AUTHORIZATION,
-                        header);
-                },
-                _ => {}
-            }
-        }
+        // Security schemes: petstore_auth
+        let response = self.send(request, context, &[
+            SecurityScheme::Bearer,
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(UpdatePetWithFormResponse::Problem);
+        }
 
         match response.status().as_u16() {
+            204 => {
+                Ok(
+                    UpdatePetWithFormResponse::PetUpdated
+                )
+            }
             405 => {
                 Ok(
                     UpdatePetWithFormResponse::InvalidInput
@@ -1161,14 +1353,14 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/store/order/{order_id}",
-            self.base_path
+            "{}{}/store/order/{order_id}",
+            self.base_path, self.api_base_path
             ,order_id=utf8_percent_encode(&param_order_id.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1195,9 +1387,16 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(DeleteOrderResponse::Problem);
+        }
+
         match response.status().as_u16() {
             400 => {
                 Ok(
@@ -1233,15 +1432,14 @@ impl<S, C> Api<C> for Client<S, C> where
         &self,
         context: &C) -> Result<GetInventoryResponse, ApiError>
     {
-        let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/store/inventory",
-            self.base_path
+            "{}{}/store/inventory",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1262,35 +1460,38 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
-        #[allow(clippy::collapsible_match)]
-        if let Some(auth_data) = Has::<Option<AuthData>>::get(context).as_ref() {
-            // Currently only authentication with Basic and Bearer are supported
-            #[allow(clippy::single_match, clippy::match_single_binding)]
-            match auth_data {
-                _ => {}
-            }
-        }
+        // Security schemes: api_key
+        let response = self.send(request, context, &[
+            SecurityScheme::ApiKey("api_key"),
+        ]).await?;
 
-        let response = client_service.call((request, context.clone()))
-            .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(GetInventoryResponse::Problem);
+        }
 
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<std::collections::HashMap<String, i32>>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<std::collections::HashMap<String, i32>>(media_type, body)?;
                 Ok(GetInventoryResponse::SuccessfulOperation
                     (body)
                 )
@@ -1322,14 +1523,14 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/store/order/{order_id}",
-            self.base_path
+            "{}{}/store/order/{order_id}",
+            self.base_path, self.api_base_path
             ,order_id=utf8_percent_encode(&param_order_id.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1350,26 +1551,40 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(GetOrderByIdResponse::Problem);
+        }
+
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::Order>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::Order>(media_type, body)?;
                 Ok(GetOrderByIdResponse::SuccessfulOperation
                     (body)
                 )
@@ -1411,13 +1626,13 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/store/order",
-            self.base_path
+            "{}{}/store/order",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1438,40 +1653,55 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
-        let body = param_order.map(|ref body| {
-            body.as_xml()
+        let body = param_order.map(|ref body| match self.request_media_type {
+            MediaType::Json => serde_json::to_string(body).expect("impossible to fail to serialize"),
+            MediaType::Xml => body.as_xml(),
+            MediaType::FormUrlEncoded => body.as_form(),
         });
 
         if let Some(body) = body {
                 *request.body_mut() = Body::from(body);
         }
 
-        let header = "application/json";
+        let header = self.request_media_type.as_str();
         request.headers_mut().insert(CONTENT_TYPE, match HeaderValue::from_str(header) {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
         });
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(PlaceOrderResponse::Problem);
+        }
+
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::Order>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::Order>(media_type, body)?;
                 Ok(PlaceOrderResponse::SuccessfulOperation
                     (body)
                 )
@@ -1508,13 +1738,13 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user",
-            self.base_path
+            "{}{}/user",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1536,58 +1766,64 @@ impl<S, C> Api<C> for Client<S, C> where
         };
 
         // Body parameter
-        let body = param_user.map(|ref body| {
-            body.as_xml()
+        let body = param_user.map(|ref body| match self.request_media_type {
+            MediaType::Json => serde_json::to_string(body).expect("impossible to fail to serialize"),
+            MediaType::Xml => body.as_xml(),
+            MediaType::FormUrlEncoded => body.as_form(),
         });
         if let Some(body) = body {
                 *request.body_mut() = Body::from(body);
         }
 
-        let header = "application/json";
+        let header = self.request_media_type.as_str();
         request.headers_mut().insert(CONTENT_TYPE, match HeaderValue::from_str(header) {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
         });
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(CreateUserResponse::Problem);
+        }
+
         match response.status().as_u16() {
-            0 => {
+            code => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
-                let body = str::from_utf8(&body)
-                    .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::User>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = if body.is_empty() {
+                    None
+                } else {
+                    let body = str::from_utf8(&body)
+                        .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
+                    Some(deserialize_response::<models::User>(media_type, body)?)
+                };
                 Ok(CreateUserResponse::SuccessfulOperation
-                    (body)
-                )
-            }
-            code => {
-                let headers = response.headers().clone();
-                let body = response.into_body()
-                       .take(100)
-                       .into_raw().await;
-                Err(ApiError(format!("Unexpected response code {}:\n{:?}\n\n{}",
-                    code,
-                    headers,
-                    match body {
-                        Ok(body) => match String::from_utf8(body) {
-                            Ok(body) => body,
-                            Err(e) => format!("<Body was not UTF8: {:?}>", e),
-                        },
-                        Err(e) => format!("<Failed to read body: {}>", e),
+                    {
+                        status: code,
+                        body,
                     }
-                )))
+                )
             }
         }
     }
@@ -1599,13 +1835,13 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/createWithList",
-            self.base_path
+            "{}{}/user/createWithList",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1638,51 +1874,51 @@ impl<S, C> Api<C> for Client<S, C> where
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
         });
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(CreateUsersWithListInputResponse::Problem);
+        }
+
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::User>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::User>(media_type, body)?;
                 Ok(CreateUsersWithListInputResponse::SuccessfulOperation
                     (body)
                 )
             }
-            0 => {
+            code => {
                 Ok(
                     CreateUsersWithListInputResponse::SuccessfulOperation_2
-                )
-            }
-            code => {
-                let headers = response.headers().clone();
-                let body = response.into_body()
-                       .take(100)
-                       .into_raw().await;
-                Err(ApiError(format!("Unexpected response code {}:\n{:?}\n\n{}",
-                    code,
-                    headers,
-                    match body {
-                        Ok(body) => match String::from_utf8(body) {
-                            Ok(body) => body,
-                            Err(e) => format!("<Body was not UTF8: {:?}>", e),
-                        },
-                        Err(e) => format!("<Failed to read body: {}>", e),
+                    {
+                        status: code,
                     }
-                )))
+                )
             }
         }
     }
@@ -1694,14 +1930,14 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/{username}",
-            self.base_path
+            "{}{}/user/{username}",
+            self.base_path, self.api_base_path
             ,username=utf8_percent_encode(&param_username.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1728,9 +1964,16 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(DeleteUserResponse::Problem);
+        }
+
         match response.status().as_u16() {
             400 => {
                 Ok(
@@ -1769,14 +2012,14 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/{username}",
-            self.base_path
+            "{}{}/user/{username}",
+            self.base_path, self.api_base_path
             ,username=utf8_percent_encode(&param_username.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -1797,26 +2040,40 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Json,
+            MediaType::Xml,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(GetUserByNameResponse::Problem);
+        }
+
         match response.status().as_u16() {
             200 => {
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                let body = serde_json::from_str::<models::User>(body).map_err(|e| {
-                    ApiError(format!("Response body did not match the schema: {}", e))
-                })?;
+                let body = deserialize_response::<models::User>(media_type, body)?;
                 Ok(GetUserByNameResponse::SuccessfulOperation
                     (body)
                 )
@@ -1859,13 +2116,13 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/login",
-            self.base_path
+            "{}{}/user/login",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             if let Some(param_username) = param_username {
                 query_string.append_pair("username",
                     &param_username);
@@ -1894,15 +2151,30 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
+        let header = self.accept_header(&[
+            MediaType::Xml,
+            MediaType::Json,
+        ]);
+        request.headers_mut().insert(ACCEPT, match HeaderValue::from_str(&header) {
+            Ok(h) => h,
+            Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
+        });
         let header = HeaderValue::from_str(Has::<XSpanIdString>::get(context).0.as_str());
         request.headers_mut().insert(HeaderName::from_static("x-span-id"), match header {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(LoginUserResponse::Problem);
+        }
+
         match response.status().as_u16() {
             200 => {
                 let response_x_rate_limit = match response.headers().get(HeaderName::from_static("x-rate-limit")) {
@@ -1933,16 +2205,14 @@ impl<S, C> Api<C> for Client<S, C> where
                     None => None,
                 };
 
+                let media_type = response_media_type(response.headers());
                 let body = response.into_body();
                 let body = body
                         .into_raw()
                         .map_err(|e| ApiError(format!("Failed to read response: {}", e))).await?;
                 let body = str::from_utf8(&body)
                     .map_err(|e| ApiError(format!("Response was not valid UTF8: {}", e)))?;
-                // ToDo: this will move to swagger-rs and become a standard From conversion trait
-                // once https://github.com/RReverser/serde-xml-rs/pull/45 is accepted upstream
-                let body = serde_xml_rs::from_str::<String>(body)
-                    .map_err(|e| ApiError(format!("Response body did not match the schema: {}", e)))?;
+                let body = deserialize_response::<String>(media_type, body)?;
                 Ok(LoginUserResponse::SuccessfulOperation
                     {
                         body,
@@ -1982,13 +2252,13 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/logout",
-            self.base_path
+            "{}{}/user/logout",
+            self.base_path, self.api_base_path
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -2015,31 +2285,28 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: api_key, session - a session token is the context's to send,
+        // never the token provider's
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[
+            SecurityScheme::ApiKey("api_key"),
+            SecurityScheme::Bearer,
+        ])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(LogoutUserResponse::Problem);
+        }
+
         match response.status().as_u16() {
-            0 => {
+            code => {
                 Ok(
                     LogoutUserResponse::SuccessfulOperation
-                )
-            }
-            code => {
-                let headers = response.headers().clone();
-                let body = response.into_body()
-                       .take(100)
-                       .into_raw().await;
-                Err(ApiError(format!("Unexpected response code {}:\n{:?}\n\n{}",
-                    code,
-                    headers,
-                    match body {
-                        Ok(body) => match String::from_utf8(body) {
-                            Ok(body) => body,
-                            Err(e) => format!("<Body was not UTF8: {:?}>", e),
-                        },
-                        Err(e) => format!("<Failed to read body: {}>", e),
+                    {
+                        status: code,
                     }
-                )))
+                )
             }
         }
     }
@@ -2052,14 +2319,14 @@ impl<S, C> Api<C> for Client<S, C> where
     {
         let mut client_service = self.client_service.clone();
         let mut uri = format!(
-            "{}/api/v3/user/{username}",
-            self.base_path
+            "{}{}/user/{username}",
+            self.base_path, self.api_base_path
             ,username=utf8_percent_encode(&param_username.to_string(), ID_ENCODE_SET)
         );
 
         // Query parameters
         let query_string = {
-            let mut query_string = form_urlencoded::Serializer::new("".to_owned());
+            let mut query_string = QuerySerializer::new();
             query_string.finish()
         };
         if !query_string.is_empty() {
@@ -2080,15 +2347,17 @@ impl<S, C> Api<C> for Client<S, C> where
                 Err(e) => return Err(ApiError(format!("Unable to create request: {}", e)))
         };
 
-        let body = param_user.map(|ref body| {
-            body.as_xml()
+        let body = param_user.map(|ref body| match self.request_media_type {
+            MediaType::Json => serde_json::to_string(body).expect("impossible to fail to serialize"),
+            MediaType::Xml => body.as_xml(),
+            MediaType::FormUrlEncoded => body.as_form(),
         });
 
         if let Some(body) = body {
                 *request.body_mut() = Body::from(body);
         }
 
-        let header = "application/json";
+        let header = self.request_media_type.as_str();
         request.headers_mut().insert(CONTENT_TYPE, match HeaderValue::from_str(header) {
             Ok(h) => h,
             Err(e) => return Err(ApiError(format!("Unable to create header: {} - {}", header, e)))
@@ -2100,31 +2369,24 @@ impl<S, C> Api<C> for Client<S, C> where
             Err(e) => return Err(ApiError(format!("Unable to create X-Span ID header value: {}", e)))
         });
 
+        // Security schemes: none
+        add_credentials(&mut request, Has::<Option<AuthData>>::get(context).as_ref(), &[])?;
+
         let response = client_service.call((request, context.clone()))
             .map_err(|e| ApiError(format!("No response received: {}", e))).await?;
 
+        if is_problem(response.headers()) {
+            return read_problem(response).await.map(UpdateUserResponse::Problem);
+        }
+
         match response.status().as_u16() {
-            0 => {
+            code => {
                 Ok(
                     UpdateUserResponse::SuccessfulOperation
-                )
-            }
-            code => {
-                let headers = response.headers().clone();
-                let body = response.into_body()
-                       .take(100)
-                       .into_raw().await;
-                Err(ApiError(format!("Unexpected response code {}:\n{:?}\n\n{}",
-                    code,
-                    headers,
-                    match body {
-                        Ok(body) => match String::from_utf8(body) {
-                            Ok(body) => body,
-                            Err(e) => format!("<Body was not UTF8: {:?}>", e),
-                        },
-                        Err(e) => format!("<Failed to read body: {}>", e),
+                    {
+                        status: code,
                     }
-                )))
+                )
             }
         }
     }
@@ -0,0 +1,350 @@
+//! Access tokens for the `petstore_auth` OAuth2 scheme.
+//!
+//! A `TokenProvider` given to `Client::with_token_provider` supplies the bearer token for
+//! operations that accept one, whenever the context carries no credentials of its own:
+//!
+//! ```ignore
+//! let endpoint = TokenEndpoint::new("https://auth.example.com/oauth/token", "petstore-cli")?
+//!     .with_client_secret(secret);
+//! let client = Client::try_new_https(&base_url)?
+//!     .with_token_provider(ClientCredentials::new(endpoint).with_scopes(&["read:pets"]));
+//! ```
+//!
+//! Tokens are cached until shortly before they expire. If the server rejects a token with
+//! a 401, the client discards it, fetches another and sends the request once more.
+
+use async_trait::async_trait;
+use futures::lock::Mutex;
+use hyper::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+use hyper::{Body, Request, StatusCode, Uri, service::Service};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use swagger::{ApiError, AuthData, BodyExt};
+use url::form_urlencoded;
+
+use super::{add_credentials, ClientInitError, HyperClient, SecurityScheme};
+
+/// How long before a token's expiry it stops being used, so that it doesn't expire in
+/// flight.
+pub const EXPIRY_MARGIN_SECS: u64 = 30;
+
+/// Supplies access tokens to a `Client`.
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// A token to send, fetching a new one if there's no valid token cached.
+    async fn token(&self) -> Result<String, ApiError>;
+
+    /// Discard `token`, which the server rejected, so that the next call to `token`
+    /// fetches a new one. Tokens that can't be replaced are kept.
+    async fn invalidate(&self, token: &str);
+}
+
+#[async_trait]
+impl<T: TokenProvider + ?Sized> TokenProvider for Arc<T> {
+    async fn token(&self) -> Result<String, ApiError> {
+        (**self).token().await
+    }
+
+    async fn invalidate(&self, token: &str) {
+        (**self).invalidate(token).await
+    }
+}
+
+/// A token obtained elsewhere, sent as it is. It's never refreshed.
+#[derive(Clone)]
+pub struct StaticToken(pub String);
+
+impl fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticToken(..)")
+    }
+}
+
+#[async_trait]
+impl TokenProvider for StaticToken {
+    async fn token(&self) -> Result<String, ApiError> {
+        Ok(self.0.clone())
+    }
+
+    async fn invalidate(&self, _token: &str) {}
+}
+
+/// An OAuth2 token endpoint, and the client that authenticates to it.
+///
+/// The client's credentials are sent as HTTP Basic credentials, as RFC 6749 requires
+/// every token endpoint to accept. A public client, without a secret, sends only its
+/// `client_id`, in the request body.
+#[derive(Clone)]
+pub struct TokenEndpoint {
+    uri: Uri,
+    client_id: String,
+    client_secret: Option<String>,
+    client_service: HyperClient,
+}
+
+impl fmt::Debug for TokenEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenEndpoint")
+            .field("uri", &self.uri)
+            .field("client_id", &self.client_id)
+            .finish()
+    }
+}
+
+/// A successful token response, from RFC 6749 section 5.1.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+/// An error response, from RFC 6749 section 5.2.
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl TokenEndpoint {
+    /// Request tokens from `url` as the client `client_id`. Both `http` and `https` URLs
+    /// are accepted.
+    pub fn new(url: &str, client_id: impl Into<String>) -> Result<Self, ClientInitError> {
+        let uri = Uri::from_str(url)?;
+        let scheme = uri.scheme_str().ok_or(ClientInitError::InvalidScheme)?;
+        let client_service = HyperClient::try_new(&scheme.to_ascii_lowercase())?;
+        Ok(TokenEndpoint {
+            uri,
+            client_id: client_id.into(),
+            client_secret: None,
+            client_service,
+        })
+    }
+
+    /// Authenticate as a confidential client, with `client_secret`.
+    pub fn with_client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Ask for a token with the given form parameters.
+    async fn request(&self, params: &[(&str, &str)]) -> Result<TokenResponse, ApiError> {
+        let body = {
+            let mut body = form_urlencoded::Serializer::new(String::new());
+            body.extend_pairs(params);
+            if self.client_secret.is_none() {
+                body.append_pair("client_id", &self.client_id);
+            }
+            body.finish()
+        };
+
+        let mut request = match Request::builder()
+            .method("POST")
+            .uri(self.uri.clone())
+            .body(Body::from(body)) {
+                Ok(req) => req,
+                Err(e) => return Err(ApiError(format!("Unable to create token request: {}", e)))
+        };
+        request.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"));
+        request.headers_mut().insert(ACCEPT, HeaderValue::from_static("application/json"));
+        if let Some(ref client_secret) = self.client_secret {
+            // RFC 6749 section 2.3.1 form-encodes the credentials before they're combined
+            let client_id = form_urlencoded::byte_serialize(self.client_id.as_bytes()).collect::<String>();
+            let client_secret = form_urlencoded::byte_serialize(client_secret.as_bytes()).collect::<String>();
+            add_credentials(&mut request, Some(&AuthData::basic(&client_id, &client_secret)), &[
+                SecurityScheme::Basic,
+            ])?;
+        }
+
+        let response = self.client_service.clone().call(request).await
+            .map_err(|e| ApiError(format!("No response received from token endpoint: {}", e)))?;
+        let status = response.status();
+        let body = response.into_body()
+            .into_raw().await
+            .map_err(|e| ApiError(format!("Failed to read token response: {}", e)))?;
+
+        if status != StatusCode::OK {
+            return Err(match serde_json::from_slice::<TokenErrorResponse>(&body) {
+                Ok(error) => ApiError(format!("Token endpoint refused the request ({}): {}{}",
+                    status,
+                    error.error,
+                    error.error_description.map(|description| format!(" - {}", description)).unwrap_or_default())),
+                Err(_) => ApiError(format!("Token endpoint returned {}", status)),
+            });
+        }
+
+        let token = serde_json::from_slice::<TokenResponse>(&body)
+            .map_err(|e| ApiError(format!("Token response did not match the schema: {}", e)))?;
+        if !token.token_type.eq_ignore_ascii_case("bearer") {
+            return Err(ApiError(format!("Token endpoint issued an unsupported {} token", token.token_type)));
+        }
+        Ok(token)
+    }
+}
+
+/// A token, and when it stops being used.
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    /// `None` if the token endpoint didn't say when it expires
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn new(response: &TokenResponse) -> Self {
+        CachedToken {
+            token: response.access_token.clone(),
+            expires_at: response.expires_in
+                .map(|expires_in| Instant::now() + Duration::from_secs(expires_in.saturating_sub(EXPIRY_MARGIN_SECS))),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.expires_at.map_or(true, |expires_at| Instant::now() < expires_at)
+    }
+}
+
+/// Space-separated scopes, as the `scope` parameter expects.
+fn scope_parameter(scopes: &[String]) -> Option<String> {
+    if scopes.is_empty() {
+        None
+    } else {
+        Some(scopes.join(" "))
+    }
+}
+
+/// Tokens from the client credentials grant, for a client acting on its own behalf.
+#[derive(Debug)]
+pub struct ClientCredentials {
+    endpoint: TokenEndpoint,
+    scopes: Vec<String>,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl ClientCredentials {
+    /// Ask `endpoint` for tokens with the scopes that it grants by default.
+    pub fn new(endpoint: TokenEndpoint) -> Self {
+        ClientCredentials {
+            endpoint,
+            scopes: Vec::new(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Ask for tokens with `scopes`, e.g. `read:pets`.
+    pub fn with_scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+}
+
+#[async_trait]
+impl TokenProvider for ClientCredentials {
+    async fn token(&self) -> Result<String, ApiError> {
+        // Held while fetching, so that concurrent requests share one new token
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref().filter(|token| token.is_valid()) {
+            return Ok(token.token.clone());
+        }
+
+        let scope = scope_parameter(&self.scopes);
+        let mut params = vec![("grant_type", "client_credentials")];
+        if let Some(ref scope) = scope {
+            params.push(("scope", scope));
+        }
+        let response = self.endpoint.request(&params).await?;
+
+        let token = CachedToken::new(&response);
+        *cached = Some(token.clone());
+        Ok(token.token)
+    }
+
+    async fn invalidate(&self, token: &str) {
+        let mut cached = self.cached.lock().await;
+        if cached.as_ref().map_or(false, |cached| cached.token == token) {
+            *cached = None;
+        }
+    }
+}
+
+/// The refresh token, and the access token most recently obtained with it.
+#[derive(Debug)]
+struct RefreshState {
+    refresh_token: String,
+    cached: Option<CachedToken>,
+}
+
+/// Tokens from the refresh token grant, for a client acting on a user's behalf with a
+/// refresh token obtained when the user authorized it.
+///
+/// If the token endpoint rotates refresh tokens, the new one is used from then on, and
+/// `refresh_token` returns it so that it can be saved.
+#[derive(Debug)]
+pub struct RefreshToken {
+    endpoint: TokenEndpoint,
+    scopes: Vec<String>,
+    state: Mutex<RefreshState>,
+}
+
+impl RefreshToken {
+    /// Exchange `refresh_token` at `endpoint` for access tokens.
+    pub fn new(endpoint: TokenEndpoint, refresh_token: impl Into<String>) -> Self {
+        RefreshToken {
+            endpoint,
+            scopes: Vec::new(),
+            state: Mutex::new(RefreshState {
+                refresh_token: refresh_token.into(),
+                cached: None,
+            }),
+        }
+    }
+
+    /// Ask for tokens with `scopes`, which must have been granted with the refresh token.
+    pub fn with_scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    /// The current refresh token.
+    pub async fn refresh_token(&self) -> String {
+        self.state.lock().await.refresh_token.clone()
+    }
+}
+
+#[async_trait]
+impl TokenProvider for RefreshToken {
+    async fn token(&self) -> Result<String, ApiError> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = state.cached.as_ref().filter(|token| token.is_valid()) {
+            return Ok(token.token.clone());
+        }
+
+        let scope = scope_parameter(&self.scopes);
+        let mut params = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", state.refresh_token.as_str()),
+        ];
+        if let Some(ref scope) = scope {
+            params.push(("scope", scope));
+        }
+        let response = self.endpoint.request(&params).await?;
+
+        let token = CachedToken::new(&response);
+        if let Some(refresh_token) = response.refresh_token {
+            state.refresh_token = refresh_token;
+        }
+        state.cached = Some(token.clone());
+        Ok(token.token)
+    }
+
+    async fn invalidate(&self, token: &str) {
+        let mut state = self.state.lock().await;
+        if state.cached.as_ref().map_or(false, |cached| cached.token == token) {
+            state.cached = None;
+        }
+    }
+}
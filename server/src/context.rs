@@ -8,6 +8,7 @@ use std::marker::PhantomData;
 use std::task::{Poll, Context};
 use swagger::auth::{AuthData, Authorization, Bearer, Scopes};
 use swagger::{EmptyContext, Has, Pop, Push, XSpanIdString};
+use tower::Layer;
 use crate::Api;
 
 pub struct MakeAddContext<T, A> {
@@ -83,6 +84,60 @@ where
     }
 }
 
+impl<T, A, B, C, D> Clone for AddContext<T, A, B, C, D>
+where
+    A: Default + Push<XSpanIdString, Result = B>,
+    B: Push<Option<AuthData>, Result = C>,
+    C: Push<Option<Authorization>, Result = D>,
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        AddContext {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// A `tower::Layer` that wraps services in `AddContext`, building contexts of type `A`
+/// from each request.
+pub struct AddContextLayer<A> {
+    marker: PhantomData<fn(A)>,
+}
+
+impl<A> AddContextLayer<A> {
+    pub fn new() -> Self {
+        AddContextLayer {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A> Default for AddContextLayer<A> {
+    fn default() -> Self {
+        AddContextLayer::new()
+    }
+}
+
+impl<A> Clone for AddContextLayer<A> {
+    fn clone(&self) -> Self {
+        AddContextLayer::new()
+    }
+}
+
+impl<T, A, B, C, D> Layer<T> for AddContextLayer<A>
+where
+    A: Default + Push<XSpanIdString, Result = B>,
+    B: Push<Option<AuthData>, Result = C>,
+    C: Push<Option<Authorization>, Result = D>,
+{
+    type Service = AddContext<T, A, B, C, D>;
+
+    fn layer(&self, inner: T) -> Self::Service {
+        AddContext::new(inner)
+    }
+}
+
 impl<T, A, B, C, D, ReqBody> Service<Request<ReqBody>> for AddContext<T, A, B, C, D>
     where
         A: Default + Push<XSpanIdString, Result=B>,
@@ -1,24 +1,244 @@
+//! Cross-origin resource sharing (CORS) middleware.
+
 use futures::FutureExt;
 use futures::future::BoxFuture;
-use hyper::header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN};
+use hyper::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCESS_CONTROL_ALLOW_CREDENTIALS,
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD,
+    AUTHORIZATION, CONTENT_TYPE, ORIGIN, VARY,
+};
 use hyper::service::Service;
-use hyper::Request;
+use hyper::{Method, Request, Response, StatusCode};
+use regex::Regex;
+use std::sync::Arc;
 use std::task::{Poll, Context};
+use std::time::Duration;
+use tower::Layer;
+
+/// An origin, or set of origins, that may make cross-origin requests.
+#[derive(Debug, Clone)]
+enum AllowedOrigin {
+    Any,
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl AllowedOrigin {
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(allowed) => allowed == origin,
+            AllowedOrigin::Pattern(pattern) => pattern.is_match(origin),
+        }
+    }
+}
+
+/// Which cross-origin requests are allowed, and what browsers may do with their responses.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    origins: Vec<AllowedOrigin>,
+    methods: Vec<Method>,
+    allow_headers: Vec<HeaderName>,
+    expose_headers: Vec<HeaderName>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl Default for CorsConfig {
+    /// Allow any origin to call the API, without credentials.
+    fn default() -> Self {
+        CorsConfig::new().allow_any_origin()
+    }
+}
+
+impl CorsConfig {
+    /// A configuration that allows no origins yet. The methods that the API's operations
+    /// use and the request headers that it reads (`Accept`, `Authorization`, `Content-Type`
+    /// and `api_key`) are allowed.
+    pub fn new() -> Self {
+        CorsConfig {
+            origins: Vec::new(),
+            methods: vec![Method::GET, Method::HEAD, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS],
+            allow_headers: vec![ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderName::from_static("api_key")],
+            expose_headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+
+    /// Allow requests from any origin.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.origins.push(AllowedOrigin::Any);
+        self
+    }
+
+    /// Allow requests from exactly `origin`, e.g. `https://app.example.com`.
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origins.push(AllowedOrigin::Exact(origin.into()));
+        self
+    }
+
+    /// Allow requests from origins matching `pattern`, in which `*` stands for one or
+    /// more host labels - so `https://*.example.com` allows `https://app.example.com`, but
+    /// not `https://example.com`.
+    pub fn allow_origin_wildcard(mut self, pattern: &str) -> Self {
+        let pattern = pattern.split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(r"[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*");
+        let pattern = Regex::new(&format!("^{}$", pattern)).expect("Unable to create regex for wildcard origin");
+        self.origins.push(AllowedOrigin::Pattern(pattern));
+        self
+    }
+
+    /// Allow requests from origins matching the regular expression `pattern`, which must
+    /// match the whole origin.
+    pub fn allow_origin_regex(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        let pattern = Regex::new(&format!("^(?:{})$", pattern))?;
+        self.origins.push(AllowedOrigin::Pattern(pattern));
+        Ok(self)
+    }
+
+    /// Set the methods that cross-origin requests may use.
+    pub fn allow_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Set the request headers that cross-origin requests may send.
+    pub fn allow_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.allow_headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Set the response headers, beyond the CORS-safelisted ones, that browsers expose to
+    /// cross-origin callers.
+    pub fn expose_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.expose_headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Whether cross-origin requests may include credentials, such as cookies.
+    ///
+    /// Browsers reject `Access-Control-Allow-Origin: *` on such requests, so the
+    /// request's origin is echoed back instead, even when any origin is allowed.
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// How long browsers may cache the result of a preflight request.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Whether every response gets `Access-Control-Allow-Origin: *`, so that responses
+    /// don't vary by origin.
+    fn allows_all(&self) -> bool {
+        !self.credentials && self.origins.iter().any(|origin| matches!(origin, AllowedOrigin::Any))
+    }
+
+    /// The `Access-Control-Allow-Origin` value for a request from `origin`, if it's allowed.
+    fn allowed_origin(&self, origin: Option<&HeaderValue>) -> Option<HeaderValue> {
+        if self.allows_all() {
+            return Some(HeaderValue::from_static("*"));
+        }
+
+        let origin = origin?;
+        let allowed = origin.to_str().map_or(false, |value|
+            self.origins.iter().any(|allowed| allowed.matches(value)));
+        if allowed {
+            Some(origin.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Add the headers common to preflight and actual responses, returning whether the
+    /// origin is allowed.
+    fn add_origin_headers(&self, headers: &mut HeaderMap, origin: Option<&HeaderValue>) -> bool {
+        if !self.allows_all() {
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+        }
+
+        match self.allowed_origin(origin) {
+            Some(allowed) => {
+                headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+                if self.credentials {
+                    headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn add_preflight_headers(&self, headers: &mut HeaderMap, origin: Option<&HeaderValue>) {
+        if !self.add_origin_headers(headers, origin) {
+            return;
+        }
+
+        if let Some(methods) = join(self.methods.iter().map(Method::as_str)) {
+            headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+        }
+        if let Some(allow_headers) = join(self.allow_headers.iter().map(HeaderName::as_str)) {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+        }
+        if let Some(max_age) = self.max_age {
+            headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age.as_secs()));
+        }
+    }
+
+    fn add_response_headers(&self, headers: &mut HeaderMap, origin: Option<&HeaderValue>) {
+        if !self.add_origin_headers(headers, origin) {
+            return;
+        }
+
+        if let Some(expose_headers) = join(self.expose_headers.iter().map(HeaderName::as_str)) {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, expose_headers);
+        }
+    }
+}
+
+/// Join header list items, if there are any.
+fn join<'a>(items: impl Iterator<Item = &'a str>) -> Option<HeaderValue> {
+    let items = items.collect::<Vec<_>>();
+    if items.is_empty() {
+        return None;
+    }
+    HeaderValue::from_str(&items.join(", ")).ok()
+}
+
+fn is_preflight<B>(request: &Request<B>) -> bool {
+    request.method() == Method::OPTIONS &&
+        request.headers().contains_key(ORIGIN) &&
+        request.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+}
 
 #[derive(Debug, Clone)]
 pub struct MakeAddAccessControlOrigin<T>
 {
-    inner: T
+    inner: T,
+    config: Arc<CorsConfig>,
 }
 
 impl<T> MakeAddAccessControlOrigin<T>
 {
-    /// Create a middleware that authorizes with the configured subject.
+    /// Create a middleware that allows cross-origin requests from any origin.
     pub fn new(inner: T) -> Self {
         MakeAddAccessControlOrigin {
-            inner
+            inner,
+            config: Arc::new(CorsConfig::default()),
         }
     }
+
+    /// Allow cross-origin requests as `config` describes.
+    pub fn with_config(mut self, config: CorsConfig) -> Self {
+        self.config = Arc::new(config);
+        self
+    }
 }
 
 impl<T, Target> Service<Target> for MakeAddAccessControlOrigin<T>
@@ -35,27 +255,66 @@ where
     }
 
     fn call(&mut self, target: Target) -> Self::Future {
+        let config = self.config.clone();
         Box::pin(
             self.inner
                 .call(target)
-                .map(|s| Ok(AddAccessControlOrigin { inner: s? })),
+                .map(|s| Ok(AddAccessControlOrigin { inner: s?, config })),
         )
     }
 }
 
+/// A `tower::Layer` that wraps services in `AddAccessControlOrigin`.
+#[derive(Debug, Clone)]
+pub struct CorsLayer {
+    config: Arc<CorsConfig>,
+}
+
+impl CorsLayer {
+    /// Allow cross-origin requests as `config` describes.
+    pub fn new(config: CorsConfig) -> Self {
+        CorsLayer {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl Default for CorsLayer {
+    /// Allow cross-origin requests from any origin.
+    fn default() -> Self {
+        CorsLayer::new(CorsConfig::default())
+    }
+}
+
+impl<S> Layer<S> for CorsLayer {
+    type Service = AddAccessControlOrigin<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddAccessControlOrigin {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Adds CORS headers to responses, and answers preflight requests without passing them
+/// on to the inner service.
 #[derive(Debug, Clone)]
 pub struct AddAccessControlOrigin<T>
 {
-    inner: T
+    inner: T,
+    config: Arc<CorsConfig>,
 }
 
 
 impl<T, B, RC> Service<(Request<B>, RC)> for AddAccessControlOrigin<T>
 where
-    T: Service<(Request<B>, RC), Response=hyper::Response<B>>,
-    T::Future: Send + 'static
+    T: Service<(Request<B>, RC), Response=Response<B>>,
+    T::Future: Send + 'static,
+    T::Error: Send + 'static,
+    B: Default + Send + 'static,
 {
-    type Response = hyper::Response<B>;
+    type Response = Response<B>;
     type Error = T::Error;
     type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -64,11 +323,21 @@ where
     }
 
     fn call(&mut self, req: (Request<B>, RC)) -> Self::Future {
+        let origin = req.0.headers().get(ORIGIN).cloned();
+
+        if is_preflight(&req.0) {
+            let mut response = Response::new(B::default());
+            *response.status_mut() = StatusCode::NO_CONTENT;
+            self.config.add_preflight_headers(response.headers_mut(), origin.as_ref());
+            return Box::pin(futures::future::ok(response));
+        }
+
+        let config = self.config.clone();
         let response = self.inner.call(req);
-        Box::pin(response.map(|response| {
+        Box::pin(response.map(move |response| {
             let mut response = response?;
-            response.headers_mut().insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+            config.add_response_headers(response.headers_mut(), origin.as_ref());
             Ok(response)
         }))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,359 @@
+//! `application/x-www-form-urlencoded` bodies, encoded as the OpenAPI specification
+//! describes for request bodies.
+//!
+//! Every property uses `style: form` with `explode: true`, so arrays of primitives are
+//! sent as repeated keys (`photoUrls=a&photoUrls=b`). Nested objects use the
+//! `deepObject` style (`category[id]=1&category[name]=Dogs`), and arrays of objects are
+//! indexed (`tags[0][id]=1&tags[0][name]=friendly`).
+
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Serialize};
+use std::fmt;
+use url::form_urlencoded;
+
+/// Error produced while encoding or decoding a form body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Serialize `value` as a form body.
+pub fn to_string<T: Serialize>(value: &T) -> Result<String, Error> {
+    let value = serde_json::to_value(value).map_err(de::Error::custom)?;
+    if !value.is_object() {
+        return Err(Error("Only objects can be encoded as a form body".to_string()));
+    }
+
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    flatten("", &value, &mut serializer);
+    Ok(serializer.finish())
+}
+
+fn flatten(key: &str, value: &serde_json::Value, serializer: &mut form_urlencoded::Serializer<String>) {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => {},
+        Value::Bool(value) => { serializer.append_pair(key, &value.to_string()); },
+        Value::Number(value) => { serializer.append_pair(key, &value.to_string()); },
+        Value::String(value) => { serializer.append_pair(key, value); },
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                match item {
+                    Value::Array(_) | Value::Object(_) =>
+                        flatten(&format!("{}[{}]", key, index), item, serializer),
+                    _ => flatten(key, item, serializer),
+                }
+            }
+        },
+        Value::Object(properties) => {
+            for (name, property) in properties {
+                let name = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}[{}]", key, name)
+                };
+                flatten(&name, property, serializer);
+            }
+        },
+    }
+}
+
+/// Deserialize a form body into `T`.
+pub fn from_bytes<T: DeserializeOwned>(input: &[u8]) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_bytes(input)?)
+}
+
+/// A form body parsed into a tree of keys, ready to be deserialized.
+#[derive(Debug)]
+pub struct Deserializer {
+    root: Node,
+}
+
+impl Deserializer {
+    /// Parse a form body, grouping `deepObject` keys and repeated keys. Fails if a key
+    /// is used both for a value and for an object, as in `a=1&a[b]=2`.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, Error> {
+        let mut root = Node::Map(Vec::new());
+        for (key, value) in form_urlencoded::parse(input) {
+            root.insert(&path(&key), value.into_owned())?;
+        }
+        Ok(Deserializer { root })
+    }
+}
+
+/// Split `tags[0][name]` into `["tags", "0", "name"]`. Empty brackets (`photoUrls[]`)
+/// are dropped, so they behave like repeated keys.
+fn path(key: &str) -> Vec<String> {
+    let (head, mut rest) = match key.find('[') {
+        Some(index) => (&key[..index], &key[index..]),
+        None => (key, ""),
+    };
+
+    let mut path = vec![head.to_string()];
+    while let Some(stripped) = rest.strip_prefix('[') {
+        match stripped.find(']') {
+            Some(end) => {
+                if end > 0 {
+                    path.push(stripped[..end].to_string());
+                }
+                rest = &stripped[end + 1..];
+            },
+            None => {
+                // Unterminated bracket - treat the remainder as part of the last key
+                if let Some(last) = path.last_mut() {
+                    last.push_str(rest);
+                }
+                break;
+            },
+        }
+    }
+    path
+}
+
+#[derive(Debug)]
+enum Node {
+    Values(Vec<String>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    /// Add a value under `path` to this object.
+    fn insert(&mut self, path: &[String], value: String) -> Result<(), Error> {
+        let entries = match self {
+            Node::Map(entries) => entries,
+            Node::Values(_) => return Err(Error("Expected an object, found a value".to_string())),
+        };
+
+        let (name, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        let index = match entries.iter().position(|(key, _)| key == name) {
+            Some(index) => index,
+            None => {
+                let node = if rest.is_empty() { Node::Values(Vec::new()) } else { Node::Map(Vec::new()) };
+                entries.push((name.clone(), node));
+                entries.len() - 1
+            },
+        };
+
+        match (&mut entries[index].1, rest.is_empty()) {
+            (Node::Values(values), true) => {
+                values.push(value);
+                Ok(())
+            },
+            (node @ Node::Map(_), false) => node.insert(rest, value),
+            _ => Err(Error(format!("Key {} is used both for a value and for an object", name))),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.root.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.root.deserialize_option(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.root.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let value = self.into_value()?;
+                match value.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(e) => Err(Error(format!("Invalid value {:?}: {}", value, e))),
+                }
+            }
+        )*
+    };
+}
+
+impl Node {
+    /// The single value held by this node.
+    fn into_value(self) -> Result<String, Error> {
+        match self {
+            Node::Values(mut values) if values.len() == 1 => Ok(values.remove(0)),
+            Node::Values(values) => Err(Error(format!("Expected a single value, found {}", values.len()))),
+            Node::Map(_) => Err(Error("Expected a value, found an object".to_string())),
+        }
+    }
+
+    /// Children of this node, as array elements.
+    fn into_elements(self) -> Vec<Node> {
+        match self {
+            Node::Values(values) => values.into_iter().map(|value| Node::Values(vec![value])).collect(),
+            Node::Map(mut entries) => {
+                // Indexed keys (`tags[0]`, `tags[1]`) - order by index rather than by appearance
+                if entries.iter().all(|(key, _)| key.parse::<usize>().is_ok()) {
+                    entries.sort_by_key(|(key, _)| key.parse::<usize>().unwrap_or_default());
+                }
+                entries.into_iter().map(|(_, node)| node).collect()
+            },
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Node {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Values(ref values) if values.len() == 1 => visitor.visit_string(self.into_value()?),
+            Node::Values(_) => self.deserialize_seq(visitor),
+            Node::Map(entries) => visitor.visit_map(de::value::MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Values(ref values) if values.iter().all(String::is_empty) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(de::value::SeqDeserializer::new(self.into_elements().into_iter()))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.into_value()?.into_deserializer())
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit_struct tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Node {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Category, Order, OrderStatus, Pet, PetStatus, Tag};
+    use chrono::{TimeZone, Utc};
+
+    fn pet() -> Pet {
+        let mut pet = Pet::new("Rex".to_string(), vec!["a.png".to_string(), "b.png".to_string()]);
+        pet.id = Some(10);
+        pet.category = Some(Category { id: Some(1), name: Some("Dogs".to_string()) });
+        pet.tags = Some(vec![
+            Tag { id: Some(1), name: Some("friendly".to_string()) },
+            Tag { id: Some(2), name: Some("house trained".to_string()) },
+        ]);
+        pet.status = Some(PetStatus::Available);
+        pet
+    }
+
+    #[test]
+    fn pet_round_trip() {
+        let pet = pet();
+        let body = to_string(&pet).unwrap();
+        assert!(body.contains("category%5Bname%5D=Dogs"), "{}", body);
+        assert!(body.contains("tags%5B1%5D%5Bname%5D=house+trained"), "{}", body);
+        assert!(body.contains("photoUrls=a.png&photoUrls=b.png"), "{}", body);
+        assert_eq!(from_bytes::<Pet>(body.as_bytes()).unwrap(), pet);
+    }
+
+    #[test]
+    fn order_round_trip() {
+        let mut order = Order::new();
+        order.id = Some(7);
+        order.pet_id = Some(10);
+        order.quantity = Some(2);
+        order.ship_date = Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        order.status = Some(OrderStatus::Approved);
+        order.complete = Some(false);
+
+        let body = to_string(&order).unwrap();
+        assert_eq!(from_bytes::<Order>(body.as_bytes()).unwrap(), order);
+    }
+
+    #[test]
+    fn indexed_keys_are_ordered_by_index() {
+        let body = "name=Rex&photoUrls[]=a.png&tags[1][name]=second&tags[0][name]=first&category[id]=1";
+        let pet = from_bytes::<Pet>(body.as_bytes()).unwrap();
+        assert_eq!(pet.photo_urls, vec!["a.png".to_string()]);
+        let names = pet.tags.unwrap().into_iter().map(|tag| tag.name.unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["first".to_string(), "second".to_string()]);
+        assert_eq!(pet.category.unwrap().id, Some(1));
+    }
+
+    #[test]
+    fn conflicting_keys_are_rejected() {
+        for body in ["a=1&a[b]=2", "a[b]=2&a=1", "a[b]=1&a[b][c]=2"] {
+            let error = Deserializer::from_bytes(body.as_bytes()).unwrap_err();
+            assert!(error.to_string().contains("used both"), "{}: {}", body, error);
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let error = from_bytes::<Pet>(b"id=ten&name=Rex&photoUrls=a.png").unwrap_err();
+        assert!(error.to_string().contains("ten"), "{}", error);
+    }
+}
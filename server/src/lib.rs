@@ -1,9 +1,12 @@
 #![allow(missing_docs, trivial_casts, unused_variables, unused_mut, unused_imports, unused_extern_crates, non_camel_case_types)]
-#![allow(unused_imports, unused_attributes)]
-#![allow(clippy::derive_partial_eq_without_eq, clippy::blacklisted_name)]
+#![allow(unused_attributes)]
+#![allow(clippy::derive_partial_eq_without_eq, clippy::disallowed_names, clippy::match_single_binding)]
+// `map_or` rather than `is_none_or` and `is_some_and`, which need a newer Rust than the rest
+// of the crate does
+#![allow(clippy::unnecessary_map_or)]
 
 use async_trait::async_trait;
-use futures::Stream;
+use futures::{Stream, TryStreamExt};
 use std::error::Error;
 use std::task::{Poll, Context};
 use swagger::{ApiError, ContextWrapper};
@@ -14,6 +17,18 @@ type ServiceError = Box<dyn Error + Send + Sync + 'static>;
 pub const BASE_PATH: &str = "/api/v3";
 pub const API_VERSION: &str = "1.0.11";
 
+/// Give a base path a leading `/` and no trailing `/`, so that it can be joined with a
+/// path template. The root path is empty.
+#[cfg(any(feature = "client", feature = "server"))]
+pub(crate) fn normalize_base_path(base_path: &str) -> String {
+    let base_path = base_path.trim_matches('/');
+    if base_path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", base_path)
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[must_use]
 pub enum AddPetResponse {
@@ -23,12 +38,24 @@ pub enum AddPetResponse {
     ,
     /// Invalid input
     InvalidInput
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
 pub enum DeletePetResponse {
+    /// Pet deleted
+    PetDeleted
+    ,
     /// Invalid pet value
     InvalidPetValue
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -40,6 +67,10 @@ pub enum FindPetsByStatusResponse {
     ,
     /// Invalid status value
     InvalidStatusValue
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -51,6 +82,10 @@ pub enum FindPetsByTagsResponse {
     ,
     /// Invalid tag value
     InvalidTagValue
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -65,6 +100,10 @@ pub enum GetPetByIdResponse {
     ,
     /// Pet not found
     PetNotFound
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -82,12 +121,24 @@ pub enum UpdatePetResponse {
     ,
     /// Validation exception
     ValidationException
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
 pub enum UpdatePetWithFormResponse {
+    /// Pet updated
+    PetUpdated
+    ,
     /// Invalid input
     InvalidInput
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -98,6 +149,10 @@ pub enum DeleteOrderResponse {
     ,
     /// Order not found
     OrderNotFound
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -105,6 +160,10 @@ pub enum GetInventoryResponse {
     /// successful operation
     SuccessfulOperation
     (std::collections::HashMap<String, i32>)
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -119,6 +178,10 @@ pub enum GetOrderByIdResponse {
     ,
     /// Order not found
     OrderNotFound
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -130,13 +193,24 @@ pub enum PlaceOrderResponse {
     ,
     /// Invalid input
     InvalidInput
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum CreateUserResponse {
-    /// successful operation
+    /// successful operation - the default response, sent with the given status code
     SuccessfulOperation
-    (models::User)
+    {
+        status: u16,
+        body: Option<models::User>
+    }
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -146,8 +220,15 @@ pub enum CreateUsersWithListInputResponse {
     SuccessfulOperation
     (models::User)
     ,
-    /// successful operation
+    /// successful operation - the default response, sent with the given status code
     SuccessfulOperation_2
+    {
+        status: u16
+    }
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -158,6 +239,10 @@ pub enum DeleteUserResponse {
     ,
     /// User not found
     UserNotFound
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -172,6 +257,10 @@ pub enum GetUserByNameResponse {
     ,
     /// User not found
     UserNotFound
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -194,20 +283,47 @@ pub enum LoginUserResponse {
     ,
     /// Invalid username/password supplied
     InvalidUsername
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum LogoutUserResponse {
-    /// successful operation
+    /// successful operation - the default response, sent with the given status code
     SuccessfulOperation
+    {
+        status: u16
+    }
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum UpdateUserResponse {
-    /// successful operation
+    /// successful operation - the default response, sent with the given status code
     SuccessfulOperation
+    {
+        status: u16
+    }
+    ,
+    /// The server reported an RFC 7807 problem instead of a documented response
+    Problem
+    (problem::Problem)
 }
 
+/// Users streamed from the body of a `create_users_with_list_input_stream` request.
+///
+/// A body that turns out to be invalid part way through ends the stream with an error.
+/// If the implementation then fails - as it does if it passes the error on with `?` - the
+/// server reports the body's error to the client, e.g. as a 422 for a schema violation.
+/// Otherwise the implementation's response is sent as it is, so an implementation that
+/// handles the error itself must say in that response that it stopped early.
+pub type UserStream = futures::stream::BoxStream<'static, Result<models::User, ApiError>>;
+
 /// API
 #[async_trait]
 #[allow(clippy::too_many_arguments, clippy::ptr_arg)]
@@ -232,7 +348,7 @@ pub trait Api<C: Send + Sync> {
     /// Finds Pets by status
     async fn find_pets_by_status(
         &self,
-        status: Option<String>,
+        status: Option<models::PetStatus>,
         context: &C) -> Result<FindPetsByStatusResponse, ApiError>;
 
     /// Finds Pets by tags
@@ -296,6 +412,23 @@ pub trait Api<C: Send + Sync> {
         user: Option<&Vec<models::User>>,
         context: &C) -> Result<CreateUsersWithListInputResponse, ApiError>;
 
+    /// Creates list of users with given input array, consuming the users as they arrive
+    /// rather than once the whole body has been read.
+    ///
+    /// The server calls this for JSON bodies. By default it collects the users and calls
+    /// `create_users_with_list_input`; override it to handle large lists incrementally.
+    async fn create_users_with_list_input_stream(
+        &self,
+        user: Option<UserStream>,
+        context: &C) -> Result<CreateUsersWithListInputResponse, ApiError>
+    {
+        let user = match user {
+            Some(user) => Some(user.try_collect::<Vec<_>>().await?),
+            None => None,
+        };
+        self.create_users_with_list_input(user.as_ref(), context).await
+    }
+
     /// Delete user
     async fn delete_user(
         &self,
@@ -354,7 +487,7 @@ pub trait ApiNoContext<C: Send + Sync> {
     /// Finds Pets by status
     async fn find_pets_by_status(
         &self,
-        status: Option<String>,
+        status: Option<models::PetStatus>,
         ) -> Result<FindPetsByStatusResponse, ApiError>;
 
     /// Finds Pets by tags
@@ -418,6 +551,12 @@ pub trait ApiNoContext<C: Send + Sync> {
         user: Option<&Vec<models::User>>,
         ) -> Result<CreateUsersWithListInputResponse, ApiError>;
 
+    /// Creates list of users with given input array, consuming the users as they arrive
+    async fn create_users_with_list_input_stream(
+        &self,
+        user: Option<UserStream>,
+        ) -> Result<CreateUsersWithListInputResponse, ApiError>;
+
     /// Delete user
     async fn delete_user(
         &self,
@@ -498,7 +637,7 @@ impl<T: Api<C> + Send + Sync, C: Clone + Send + Sync> ApiNoContext<C> for Contex
     /// Finds Pets by status
     async fn find_pets_by_status(
         &self,
-        status: Option<String>,
+        status: Option<models::PetStatus>,
         ) -> Result<FindPetsByStatusResponse, ApiError>
     {
         let context = self.context().clone();
@@ -606,6 +745,16 @@ impl<T: Api<C> + Send + Sync, C: Clone + Send + Sync> ApiNoContext<C> for Contex
         self.api().create_users_with_list_input(user, &context).await
     }
 
+    /// Creates list of users with given input array, consuming the users as they arrive
+    async fn create_users_with_list_input_stream(
+        &self,
+        user: Option<UserStream>,
+        ) -> Result<CreateUsersWithListInputResponse, ApiError>
+    {
+        let context = self.context().clone();
+        self.api().create_users_with_list_input_stream(user, &context).await
+    }
+
     /// Delete user
     async fn delete_user(
         &self,
@@ -679,7 +828,23 @@ pub mod context;
 
 pub mod models;
 
+pub mod media;
+
+pub mod problem;
+
+pub mod validate;
+
+#[cfg(any(feature = "client", feature = "server"))]
+pub mod form;
+
+#[cfg(any(feature = "client", feature = "server"))]
+pub mod query;
+
+#[cfg(feature = "server")]
 pub mod cors;
 
+#[cfg(feature = "validation")]
+pub mod spec;
+
 #[cfg(any(feature = "client", feature = "server"))]
 pub(crate) mod header;
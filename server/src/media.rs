@@ -0,0 +1,58 @@
+//! Media types exchanged on the wire by the `server` and `client` modules.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A media type that request and response bodies can be encoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    /// `application/json`
+    Json,
+    /// `application/xml`
+    Xml,
+    /// `application/x-www-form-urlencoded`
+    FormUrlEncoded,
+}
+
+impl MediaType {
+    /// The canonical `Content-Type` value for this media type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Json => "application/json",
+            MediaType::Xml => "application/xml",
+            MediaType::FormUrlEncoded => "application/x-www-form-urlencoded",
+        }
+    }
+
+    /// Map a parsed `mime::Mime` onto one of the supported media types.
+    ///
+    /// Parameters such as `charset` are ignored, `text/xml` is treated as XML and
+    /// structured syntax suffixes (`+json`, `+xml`) are honoured.
+    pub fn from_mime(mime: &mime::Mime) -> Option<MediaType> {
+        match (mime.type_(), mime.subtype(), mime.suffix()) {
+            (mime::APPLICATION, mime::JSON, _) => Some(MediaType::Json),
+            (mime::APPLICATION, mime::XML, _) | (mime::TEXT, mime::XML, _) => Some(MediaType::Xml),
+            (mime::APPLICATION, mime::WWW_FORM_URLENCODED, _) => Some(MediaType::FormUrlEncoded),
+            (mime::APPLICATION, _, Some(mime::JSON)) => Some(MediaType::Json),
+            (mime::APPLICATION, _, Some(mime::XML)) => Some(MediaType::Xml),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mime = s.parse::<mime::Mime>()
+            .map_err(|e| format!("Unable to parse media type {}: {}", s, e))?;
+        MediaType::from_mime(&mime)
+            .ok_or_else(|| format!("Unsupported media type {}", mime.essence_str()))
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(unused_qualifications)]
 
 use crate::models;
+use crate::validate::Validate;
 #[cfg(any(feature = "client", feature = "server"))]
 use crate::header;
 
@@ -41,12 +42,12 @@ impl Address {
 /// Converts the Address value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Address {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.street.as_ref().map(|street| {
-                vec![
+                [
                     "street".to_string(),
                     street.to_string(),
                 ].join(",")
@@ -54,7 +55,7 @@ impl std::string::ToString for Address {
 
 
             self.city.as_ref().map(|city| {
-                vec![
+                [
                     "city".to_string(),
                     city.to_string(),
                 ].join(",")
@@ -62,7 +63,7 @@ impl std::string::ToString for Address {
 
 
             self.state.as_ref().map(|state| {
-                vec![
+                [
                     "state".to_string(),
                     state.to_string(),
                 ].join(",")
@@ -70,7 +71,7 @@ impl std::string::ToString for Address {
 
 
             self.zip.as_ref().map(|zip| {
-                vec![
+                [
                     "zip".to_string(),
                     zip.to_string(),
                 ].join(",")
@@ -78,7 +79,7 @@ impl std::string::ToString for Address {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -188,6 +189,11 @@ impl Address {
     }
 }
 
+impl Validate for Address {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 #[serde(rename = "##default")]
@@ -220,12 +226,12 @@ impl ApiResponse {
 /// Converts the ApiResponse value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for ApiResponse {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ApiResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.code.as_ref().map(|code| {
-                vec![
+                [
                     "code".to_string(),
                     code.to_string(),
                 ].join(",")
@@ -233,7 +239,7 @@ impl std::string::ToString for ApiResponse {
 
 
             self.r#type.as_ref().map(|r#type| {
-                vec![
+                [
                     "type".to_string(),
                     r#type.to_string(),
                 ].join(",")
@@ -241,7 +247,7 @@ impl std::string::ToString for ApiResponse {
 
 
             self.message.as_ref().map(|message| {
-                vec![
+                [
                     "message".to_string(),
                     message.to_string(),
                 ].join(",")
@@ -249,7 +255,7 @@ impl std::string::ToString for ApiResponse {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -355,6 +361,11 @@ impl ApiResponse {
     }
 }
 
+impl Validate for ApiResponse {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 #[serde(rename = "category")]
@@ -382,12 +393,12 @@ impl Category {
 /// Converts the Category value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Category {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -395,7 +406,7 @@ impl std::string::ToString for Category {
 
 
             self.name.as_ref().map(|name| {
-                vec![
+                [
                     "name".to_string(),
                     name.to_string(),
                 ].join(",")
@@ -403,7 +414,7 @@ impl std::string::ToString for Category {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -505,6 +516,11 @@ impl Category {
     }
 }
 
+impl Validate for Category {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 #[serde(rename = "customer")]
@@ -537,12 +553,12 @@ impl Customer {
 /// Converts the Customer value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Customer {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Customer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -550,7 +566,7 @@ impl std::string::ToString for Customer {
 
 
             self.username.as_ref().map(|username| {
-                vec![
+                [
                     "username".to_string(),
                     username.to_string(),
                 ].join(",")
@@ -560,7 +576,7 @@ impl std::string::ToString for Customer {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -665,6 +681,12 @@ impl Customer {
     }
 }
 
+impl Validate for Customer {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<crate::validate::Violation>) {
+        self.address.validate_at(&format!("{}/address", pointer), violations);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 #[serde(rename = "order")]
@@ -686,10 +708,9 @@ pub struct Order {
     pub ship_date: Option<chrono::DateTime::<chrono::Utc>>,
 
     /// Order Status
-    // Note: inline enums are not fully supported by openapi-generator
     #[serde(rename = "status")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub status: Option<String>,
+    pub status: Option<models::OrderStatus>,
 
     #[serde(rename = "complete")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -714,12 +735,12 @@ impl Order {
 /// Converts the Order value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Order {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -727,7 +748,7 @@ impl std::string::ToString for Order {
 
 
             self.pet_id.as_ref().map(|pet_id| {
-                vec![
+                [
                     "petId".to_string(),
                     pet_id.to_string(),
                 ].join(",")
@@ -735,7 +756,7 @@ impl std::string::ToString for Order {
 
 
             self.quantity.as_ref().map(|quantity| {
-                vec![
+                [
                     "quantity".to_string(),
                     quantity.to_string(),
                 ].join(",")
@@ -745,7 +766,7 @@ impl std::string::ToString for Order {
 
 
             self.status.as_ref().map(|status| {
-                vec![
+                [
                     "status".to_string(),
                     status.to_string(),
                 ].join(",")
@@ -753,7 +774,7 @@ impl std::string::ToString for Order {
 
 
             self.complete.as_ref().map(|complete| {
-                vec![
+                [
                     "complete".to_string(),
                     complete.to_string(),
                 ].join(",")
@@ -761,7 +782,7 @@ impl std::string::ToString for Order {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -780,7 +801,7 @@ impl std::str::FromStr for Order {
             pub pet_id: Vec<i64>,
             pub quantity: Vec<i32>,
             pub ship_date: Vec<chrono::DateTime::<chrono::Utc>>,
-            pub status: Vec<String>,
+            pub status: Vec<models::OrderStatus>,
             pub complete: Vec<bool>,
         }
 
@@ -808,7 +829,7 @@ impl std::str::FromStr for Order {
                     #[allow(clippy::redundant_clone)]
                     "shipDate" => intermediate_rep.ship_date.push(<chrono::DateTime::<chrono::Utc> as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "status" => intermediate_rep.status.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "status" => intermediate_rep.status.push(<models::OrderStatus as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "complete" => intermediate_rep.complete.push(<bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing Order".to_string())
@@ -877,6 +898,76 @@ impl Order {
     pub(crate) fn as_xml(&self) -> String {
         serde_xml_rs::to_string(&self).expect("impossible to fail to serialize")
     }
+
+    /// Helper function to allow us to convert this model to an
+    /// `application/x-www-form-urlencoded` string.
+    /// Will panic if serialisation fails.
+    #[allow(dead_code)]
+    #[cfg(any(feature = "client", feature = "server"))]
+    pub(crate) fn as_form(&self) -> String {
+        crate::form::to_string(&self).expect("impossible to fail to serialize")
+    }
+}
+
+impl Validate for Order {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<crate::validate::Violation>) {
+        if let Some(quantity) = self.quantity {
+            violations.extend(crate::validate::minimum(&format!("{}/quantity", pointer), quantity, 1).err());
+        }
+    }
+}
+
+/// Order Status
+///
+/// Serialised as its string value in every media type, so it maps to
+/// `<status>placed</status>` in XML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "conversion", derive(frunk_enum_derive::LabelledGenericEnum))]
+pub enum OrderStatus {
+    Placed,
+    Approved,
+    Delivered,
+}
+
+impl std::fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            OrderStatus::Placed => write!(f, "placed"),
+            OrderStatus::Approved => write!(f, "approved"),
+            OrderStatus::Delivered => write!(f, "delivered"),
+        }
+    }
+}
+
+impl std::str::FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "placed" => std::result::Result::Ok(OrderStatus::Placed),
+            "approved" => std::result::Result::Ok(OrderStatus::Approved),
+            "delivered" => std::result::Result::Ok(OrderStatus::Delivered),
+            _ => std::result::Result::Err(format!("Value not valid: {}", s)),
+        }
+    }
+}
+
+impl serde::Serialize for OrderStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for OrderStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Validate for OrderStatus {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -902,10 +993,9 @@ pub struct Pet {
     pub tags: Option<Vec<models::Tag>>,
 
     /// pet status in the store
-    // Note: inline enums are not fully supported by openapi-generator
     #[serde(rename = "status")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub status: Option<String>,
+    pub status: Option<models::PetStatus>,
 
 }
 
@@ -926,12 +1016,12 @@ impl Pet {
 /// Converts the Pet value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Pet {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Pet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -951,7 +1041,7 @@ impl std::string::ToString for Pet {
 
 
             self.status.as_ref().map(|status| {
-                vec![
+                [
                     "status".to_string(),
                     status.to_string(),
                 ].join(",")
@@ -959,7 +1049,7 @@ impl std::string::ToString for Pet {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -979,7 +1069,7 @@ impl std::str::FromStr for Pet {
             pub category: Vec<models::Category>,
             pub photo_urls: Vec<Vec<String>>,
             pub tags: Vec<Vec<models::Tag>>,
-            pub status: Vec<String>,
+            pub status: Vec<models::PetStatus>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -1006,7 +1096,7 @@ impl std::str::FromStr for Pet {
                     "photoUrls" => return std::result::Result::Err("Parsing a container in this style is not supported in Pet".to_string()),
                     "tags" => return std::result::Result::Err("Parsing a container in this style is not supported in Pet".to_string()),
                     #[allow(clippy::redundant_clone)]
-                    "status" => intermediate_rep.status.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "status" => intermediate_rep.status.push(<models::PetStatus as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing Pet".to_string())
                 }
             }
@@ -1073,6 +1163,77 @@ impl Pet {
     pub(crate) fn as_xml(&self) -> String {
         serde_xml_rs::to_string(&self).expect("impossible to fail to serialize")
     }
+
+    /// Helper function to allow us to convert this model to an
+    /// `application/x-www-form-urlencoded` string.
+    /// Will panic if serialisation fails.
+    #[allow(dead_code)]
+    #[cfg(any(feature = "client", feature = "server"))]
+    pub(crate) fn as_form(&self) -> String {
+        crate::form::to_string(&self).expect("impossible to fail to serialize")
+    }
+}
+
+impl Validate for Pet {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<crate::validate::Violation>) {
+        violations.extend(crate::validate::min_length(&format!("{}/name", pointer), &self.name, 1).err());
+        self.category.validate_at(&format!("{}/category", pointer), violations);
+        violations.extend(crate::validate::min_items(&format!("{}/photoUrls", pointer), &self.photo_urls, 1).err());
+        self.tags.validate_at(&format!("{}/tags", pointer), violations);
+    }
+}
+
+/// Pet status in the store
+///
+/// Serialised as its string value in every media type, so it maps to
+/// `<status>available</status>` in XML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "conversion", derive(frunk_enum_derive::LabelledGenericEnum))]
+pub enum PetStatus {
+    Available,
+    Pending,
+    Sold,
+}
+
+impl std::fmt::Display for PetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            PetStatus::Available => write!(f, "available"),
+            PetStatus::Pending => write!(f, "pending"),
+            PetStatus::Sold => write!(f, "sold"),
+        }
+    }
+}
+
+impl std::str::FromStr for PetStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "available" => std::result::Result::Ok(PetStatus::Available),
+            "pending" => std::result::Result::Ok(PetStatus::Pending),
+            "sold" => std::result::Result::Ok(PetStatus::Sold),
+            _ => std::result::Result::Err(format!("Value not valid: {}", s)),
+        }
+    }
+}
+
+impl serde::Serialize for PetStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PetStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Validate for PetStatus {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -1102,12 +1263,12 @@ impl Tag {
 /// Converts the Tag value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for Tag {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -1115,7 +1276,7 @@ impl std::string::ToString for Tag {
 
 
             self.name.as_ref().map(|name| {
-                vec![
+                [
                     "name".to_string(),
                     name.to_string(),
                 ].join(",")
@@ -1123,7 +1284,7 @@ impl std::string::ToString for Tag {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -1225,6 +1386,11 @@ impl Tag {
     }
 }
 
+impl Validate for Tag {
+    fn validate_at(&self, _pointer: &str, _violations: &mut Vec<crate::validate::Violation>) {
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 #[serde(rename = "user")]
@@ -1283,12 +1449,12 @@ impl User {
 /// Converts the User value to the Query Parameters representation (style=form, explode=false)
 /// specified in https://swagger.io/docs/specification/serialization/
 /// Should be implemented in a serde serializer
-impl std::string::ToString for User {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
             self.id.as_ref().map(|id| {
-                vec![
+                [
                     "id".to_string(),
                     id.to_string(),
                 ].join(",")
@@ -1296,7 +1462,7 @@ impl std::string::ToString for User {
 
 
             self.username.as_ref().map(|username| {
-                vec![
+                [
                     "username".to_string(),
                     username.to_string(),
                 ].join(",")
@@ -1304,7 +1470,7 @@ impl std::string::ToString for User {
 
 
             self.first_name.as_ref().map(|first_name| {
-                vec![
+                [
                     "firstName".to_string(),
                     first_name.to_string(),
                 ].join(",")
@@ -1312,7 +1478,7 @@ impl std::string::ToString for User {
 
 
             self.last_name.as_ref().map(|last_name| {
-                vec![
+                [
                     "lastName".to_string(),
                     last_name.to_string(),
                 ].join(",")
@@ -1320,7 +1486,7 @@ impl std::string::ToString for User {
 
 
             self.email.as_ref().map(|email| {
-                vec![
+                [
                     "email".to_string(),
                     email.to_string(),
                 ].join(",")
@@ -1328,7 +1494,7 @@ impl std::string::ToString for User {
 
 
             self.password.as_ref().map(|password| {
-                vec![
+                [
                     "password".to_string(),
                     password.to_string(),
                 ].join(",")
@@ -1336,7 +1502,7 @@ impl std::string::ToString for User {
 
 
             self.phone.as_ref().map(|phone| {
-                vec![
+                [
                     "phone".to_string(),
                     phone.to_string(),
                 ].join(",")
@@ -1344,7 +1510,7 @@ impl std::string::ToString for User {
 
 
             self.user_status.as_ref().map(|user_status| {
-                vec![
+                [
                     "userStatus".to_string(),
                     user_status.to_string(),
                 ].join(",")
@@ -1352,7 +1518,7 @@ impl std::string::ToString for User {
 
         ];
 
-        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+        write!(f, "{}", params.into_iter().flatten().collect::<Vec<_>>().join(","))
     }
 }
 
@@ -1476,4 +1642,21 @@ impl User {
     pub(crate) fn as_xml(&self) -> String {
         serde_xml_rs::to_string(&self).expect("impossible to fail to serialize")
     }
+
+    /// Helper function to allow us to convert this model to an
+    /// `application/x-www-form-urlencoded` string.
+    /// Will panic if serialisation fails.
+    #[allow(dead_code)]
+    #[cfg(any(feature = "client", feature = "server"))]
+    pub(crate) fn as_form(&self) -> String {
+        crate::form::to_string(&self).expect("impossible to fail to serialize")
+    }
+}
+
+impl Validate for User {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<crate::validate::Violation>) {
+        if let Some(ref email) = self.email {
+            violations.extend(crate::validate::email(&format!("{}/email", pointer), email).err());
+        }
+    }
 }
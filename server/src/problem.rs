@@ -0,0 +1,174 @@
+//! RFC 7807 problem details, returned by the server as the body of every error
+//! response it generates itself.
+
+use std::fmt;
+
+use crate::validate::Violation;
+
+/// Media type of a problem details body.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// The kinds of problem that the server reports, each identified by a `type` URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProblemType {
+    /// The request carried no usable credentials
+    Unauthenticated,
+    /// The credentials lack scopes that the operation requires
+    InsufficientScope,
+    /// The bearer token is malformed, expired or not trusted
+    InvalidToken,
+    /// A path, query or header parameter couldn't be parsed
+    InvalidParameter,
+    /// The request body couldn't be read or doesn't match the schema
+    InvalidBody,
+    /// Parameters or the request body violate constraints of the schema
+    ConstraintViolation,
+    /// The request body has fields that the schema doesn't define
+    UnknownFields,
+    /// A required request body is missing
+    MissingBody,
+    /// The request body is larger than the operation accepts
+    PayloadTooLarge,
+    /// The request body's media type isn't accepted by the operation
+    UnsupportedMediaType,
+    /// None of the response's representations are acceptable to the client
+    NotAcceptable,
+    /// No operation matches the request path
+    NotFound,
+    /// The request path doesn't support the request method
+    MethodNotAllowed,
+    /// The server failed to handle the request
+    InternalError,
+}
+
+impl ProblemType {
+    const ALL: &'static [ProblemType] = &[
+        ProblemType::Unauthenticated,
+        ProblemType::InsufficientScope,
+        ProblemType::InvalidToken,
+        ProblemType::InvalidParameter,
+        ProblemType::InvalidBody,
+        ProblemType::ConstraintViolation,
+        ProblemType::UnknownFields,
+        ProblemType::MissingBody,
+        ProblemType::PayloadTooLarge,
+        ProblemType::UnsupportedMediaType,
+        ProblemType::NotAcceptable,
+        ProblemType::NotFound,
+        ProblemType::MethodNotAllowed,
+        ProblemType::InternalError,
+    ];
+
+    /// The `type` URI identifying this kind of problem.
+    pub fn uri(&self) -> &'static str {
+        match self {
+            ProblemType::Unauthenticated => "urn:problem-type:petstore:unauthenticated",
+            ProblemType::InsufficientScope => "urn:problem-type:petstore:insufficient-scope",
+            ProblemType::InvalidToken => "urn:problem-type:petstore:invalid-token",
+            ProblemType::InvalidParameter => "urn:problem-type:petstore:invalid-parameter",
+            ProblemType::InvalidBody => "urn:problem-type:petstore:invalid-body",
+            ProblemType::ConstraintViolation => "urn:problem-type:petstore:constraint-violation",
+            ProblemType::UnknownFields => "urn:problem-type:petstore:unknown-fields",
+            ProblemType::MissingBody => "urn:problem-type:petstore:missing-body",
+            ProblemType::PayloadTooLarge => "urn:problem-type:petstore:payload-too-large",
+            ProblemType::UnsupportedMediaType => "urn:problem-type:petstore:unsupported-media-type",
+            ProblemType::NotAcceptable => "urn:problem-type:petstore:not-acceptable",
+            ProblemType::NotFound => "urn:problem-type:petstore:not-found",
+            ProblemType::MethodNotAllowed => "urn:problem-type:petstore:method-not-allowed",
+            ProblemType::InternalError => "urn:problem-type:petstore:internal-error",
+        }
+    }
+
+    /// A short, human-readable summary of this kind of problem.
+    pub fn title(&self) -> &'static str {
+        match self {
+            ProblemType::Unauthenticated => "Unauthenticated",
+            ProblemType::InsufficientScope => "Insufficient authorization",
+            ProblemType::InvalidToken => "Invalid token",
+            ProblemType::InvalidParameter => "Invalid parameter",
+            ProblemType::InvalidBody => "Invalid request body",
+            ProblemType::ConstraintViolation => "Constraint violation",
+            ProblemType::UnknownFields => "Unknown fields",
+            ProblemType::MissingBody => "Missing request body",
+            ProblemType::PayloadTooLarge => "Payload too large",
+            ProblemType::UnsupportedMediaType => "Unsupported media type",
+            ProblemType::NotAcceptable => "Not acceptable",
+            ProblemType::NotFound => "Not found",
+            ProblemType::MethodNotAllowed => "Method not allowed",
+            ProblemType::InternalError => "Internal error",
+        }
+    }
+
+    /// Look up the kind of problem identified by a `type` URI.
+    pub fn from_uri(uri: &str) -> Option<ProblemType> {
+        ProblemType::ALL.iter().copied().find(|problem_type| problem_type.uri() == uri)
+    }
+}
+
+/// An RFC 7807 problem details object.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Problem {
+    /// URI identifying the kind of problem
+    #[serde(rename = "type", default = "about_blank")]
+    pub type_uri: String,
+
+    /// Short, human-readable summary of the kind of problem
+    #[serde(default)]
+    pub title: String,
+
+    /// HTTP status code of the response
+    pub status: u16,
+
+    /// Explanation specific to this occurrence of the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// Identifies this occurrence of the problem - the request's X-Span-ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Operation that the request was routed to, if any
+    #[serde(rename = "operationId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+
+    /// Schema constraints that the request violates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_string()
+}
+
+impl Problem {
+    /// Create a problem of the given kind, sent with `status`.
+    pub fn new(problem_type: ProblemType, status: u16, detail: impl Into<String>) -> Self {
+        Problem {
+            type_uri: problem_type.uri().to_string(),
+            title: problem_type.title().to_string(),
+            status,
+            detail: Some(detail.into()),
+            instance: None,
+            operation_id: None,
+            violations: Vec::new(),
+        }
+    }
+
+    /// The kind of problem, if it's one the server reports.
+    pub fn problem_type(&self) -> Option<ProblemType> {
+        ProblemType::from_uri(&self.type_uri)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.title, self.status)?;
+        if let Some(ref detail) = self.detail {
+            write!(f, ": {}", detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Problem {}
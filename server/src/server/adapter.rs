@@ -0,0 +1,122 @@
+//! Serving the API from inside another `tower` application, such as an axum router.
+
+use futures::future::BoxFuture;
+use hyper::{Body, Request, Response, StatusCode, Uri};
+use log::error;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::task::{Context, Poll};
+use swagger::auth::{AuthData, Authorization};
+use swagger::{Has, Push, XSpanIdString};
+
+use crate::Api;
+use crate::problem::ProblemType;
+use super::Service;
+use super::problem::Problems;
+
+/// Serves a `Service` as a `tower::Service<Request<Body>>`, which can be mounted in another
+/// router - for example with axum's `Router::nest_service`. The `Service` is configured as
+/// usual, with its base path, error mapper and limits, before it's wrapped.
+///
+/// The context of type `A` is built from the request's extensions, so middleware shared
+/// with the rest of the application can supply the `XSpanIdString`, `AuthData` and
+/// `Authorization`. A span ID that isn't in the extensions is taken from the `X-Span-ID`
+/// header, or generated.
+///
+/// Errors are always turned into responses, so the service never fails.
+pub struct ApiService<S, A> {
+    service: S,
+    prefix: Option<String>,
+    marker: PhantomData<fn(A)>,
+}
+
+impl<T, C, A> ApiService<Service<T, C>, A> where
+    T: Api<C> + Clone + Send + 'static,
+    C: Has<XSpanIdString> + Has<Option<Authorization>> + Send + Sync + 'static
+{
+    pub fn new(service: Service<T, C>) -> Self {
+        ApiService {
+            service,
+            prefix: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Strip `prefix` from request paths before routing them, for when the service is
+    /// mounted under a prefix that the outer router doesn't strip itself.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into().trim_end_matches('/').to_string());
+        self
+    }
+}
+
+impl<S, A> Clone for ApiService<S, A> where
+    S: Clone
+{
+    fn clone(&self) -> Self {
+        ApiService {
+            service: self.service.clone(),
+            prefix: self.prefix.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T, A, B, C, D> hyper::service::Service<Request<Body>> for ApiService<Service<T, D>, A> where
+    A: Default + Push<XSpanIdString, Result = B>,
+    B: Push<Option<AuthData>, Result = C>,
+    C: Push<Option<Authorization>, Result = D>,
+    D: Has<XSpanIdString> + Has<Option<Authorization>> + Send + Sync + 'static,
+    T: Api<D> + Clone + Send + Sync + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        if let Some(ref prefix) = self.prefix {
+            strip_prefix(&mut request, prefix);
+        }
+
+        let span_id = request.extensions().get::<XSpanIdString>().cloned()
+            .unwrap_or_else(|| XSpanIdString::get_or_generate(&request));
+        let auth_data = request.extensions().get::<AuthData>().cloned();
+        let authorization = request.extensions().get::<Authorization>().cloned();
+        let context = A::default()
+            .push(span_id.clone())
+            .push(auth_data)
+            .push(authorization);
+
+        let response = self.service.call((request, context));
+
+        Box::pin(async move {
+            Ok(response.await.unwrap_or_else(|e| {
+                error!("Failed to handle request - X-Span-ID: {} - {}", span_id.0, e);
+                Problems::new(None, span_id.0).response(StatusCode::INTERNAL_SERVER_ERROR, ProblemType::InternalError, "An internal error occurred")
+            }))
+        })
+    }
+}
+
+/// Remove `prefix` from the start of the request's path, if it's there.
+fn strip_prefix(request: &mut Request<Body>, prefix: &str) {
+    let path = match request.uri().path().strip_prefix(prefix) {
+        Some("") => "/",
+        Some(path) if path.starts_with('/') => path,
+        _ => return,
+    };
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+
+    let mut parts = request.uri().clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+}
@@ -0,0 +1,386 @@
+//! Authenticating requests: turning the credentials that `AddContext` extracts into the
+//! `Authorization` that the `Service` checks scopes against.
+//!
+//! API keys are looked up in a `KeyStore`, and bearer tokens are checked by
+//! `TokenValidator`s such as `jwt::JwtValidator`.
+//!
+//! Without an `Authorization`, operations that need one are rejected as
+//! unauthenticated, so this middleware sits between `AddContext` and the `Service`:
+//!
+//! ```ignore
+//! let keys = FileKeyStore::open("api-keys.json")?;
+//! let service = MakeAuthenticator::new(MakeService::new(server), keys);
+//! let service = MakeAddContext::<_, EmptyContext>::new(service);
+//! ```
+
+use futures::future::{self, BoxFuture, Either, Ready};
+use hyper::header::{HeaderValue, WWW_AUTHENTICATE};
+use hyper::service::Service;
+use hyper::{Body, Request, Response, StatusCode};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use swagger::auth::{AuthData, Authorization, Scopes};
+use swagger::{Has, Push, XSpanIdString};
+use tower::Layer;
+
+use crate::problem::ProblemType;
+use super::problem::Problems;
+
+/// Looks up API keys, as sent in the `api_key` header.
+pub trait KeyStore: Send + Sync {
+    /// What `api_key` is authorized to do, or `None` if it isn't a valid key.
+    fn authorize(&self, api_key: &str) -> Option<Authorization>;
+}
+
+impl<K: KeyStore + ?Sized> KeyStore for Arc<K> {
+    fn authorize(&self, api_key: &str) -> Option<Authorization> {
+        (**self).authorize(api_key)
+    }
+}
+
+/// Checks bearer tokens, as sent in the `Authorization` header.
+pub trait TokenValidator: Send + Sync {
+    /// What `token` is authorized to do, or why it's rejected.
+    fn validate(&self, token: &str) -> Result<Authorization, TokenError>;
+}
+
+impl<V: TokenValidator + ?Sized> TokenValidator for Arc<V> {
+    fn validate(&self, token: &str) -> Result<Authorization, TokenError> {
+        (**self).validate(token)
+    }
+}
+
+/// Why a bearer token was rejected, e.g. "Token has expired". Sent to the client as the
+/// `error_description` of the 401 response's `WWW-Authenticate` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenError(pub String);
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Who an API key belongs to and the scopes it grants, e.g. `read:pets`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyGrant {
+    pub subject: String,
+    #[serde(default)]
+    pub scopes: BTreeSet<String>,
+}
+
+impl KeyGrant {
+    pub fn new<S: Into<String>>(subject: impl Into<String>, scopes: impl IntoIterator<Item = S>) -> Self {
+        KeyGrant {
+            subject: subject.into(),
+            scopes: scopes.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    fn authorization(&self) -> Authorization {
+        Authorization {
+            subject: self.subject.clone(),
+            scopes: Scopes::Some(self.scopes.clone()),
+            issuer: None,
+        }
+    }
+}
+
+/// API keys held in memory, e.g. for tests or keys taken from the environment.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryKeyStore {
+    keys: HashMap<String, KeyGrant>,
+}
+
+impl InMemoryKeyStore {
+    pub fn new() -> Self {
+        InMemoryKeyStore::default()
+    }
+
+    /// Add a key that authorizes `subject` for `scopes`.
+    pub fn with_key<S: Into<String>>(mut self, api_key: impl Into<String>, subject: impl Into<String>, scopes: impl IntoIterator<Item = S>) -> Self {
+        self.insert(api_key, KeyGrant::new(subject, scopes));
+        self
+    }
+
+    /// Add a key, replacing any existing grant for it.
+    pub fn insert(&mut self, api_key: impl Into<String>, grant: KeyGrant) {
+        self.keys.insert(api_key.into(), grant);
+    }
+
+    /// Revoke a key, returning what it granted.
+    pub fn remove(&mut self, api_key: &str) -> Option<KeyGrant> {
+        self.keys.remove(api_key)
+    }
+}
+
+impl KeyStore for InMemoryKeyStore {
+    fn authorize(&self, api_key: &str) -> Option<Authorization> {
+        self.keys.get(api_key).map(KeyGrant::authorization)
+    }
+}
+
+/// A key file that couldn't be loaded.
+#[derive(Debug)]
+pub enum KeyStoreError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file isn't a JSON object of keys to grants
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Io(e) => write!(f, "Couldn't read key file: {}", e),
+            KeyStoreError::Json(e) => write!(f, "Couldn't parse key file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyStoreError {}
+
+impl From<io::Error> for KeyStoreError {
+    fn from(e: io::Error) -> Self {
+        KeyStoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for KeyStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeyStoreError::Json(e)
+    }
+}
+
+/// API keys loaded from a JSON file mapping each key to its grant:
+///
+/// ```json
+/// {
+///   "special-key": {"subject": "cosmo", "scopes": ["read:pets", "write:pets"]}
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FileKeyStore {
+    path: PathBuf,
+    keys: RwLock<InMemoryKeyStore>,
+}
+
+impl FileKeyStore {
+    /// Load the keys in the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KeyStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let keys = FileKeyStore::load(&path)?;
+        Ok(FileKeyStore {
+            path,
+            keys: RwLock::new(keys),
+        })
+    }
+
+    /// Load the file again, e.g. after keys are added or revoked. If it can't be loaded,
+    /// the keys already loaded are kept.
+    pub fn reload(&self) -> Result<(), KeyStoreError> {
+        let keys = FileKeyStore::load(&self.path)?;
+        *self.keys.write().expect("Key store lock poisoned") = keys;
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<InMemoryKeyStore, KeyStoreError> {
+        let keys: HashMap<String, KeyGrant> = serde_json::from_slice(&fs::read(path)?)?;
+        Ok(InMemoryKeyStore { keys })
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn authorize(&self, api_key: &str) -> Option<Authorization> {
+        self.keys.read().expect("Key store lock poisoned").authorize(api_key)
+    }
+}
+
+#[derive(Clone)]
+pub struct MakeAuthenticator<T> {
+    inner: T,
+    keys: Vec<Arc<dyn KeyStore>>,
+    bearer: Vec<Arc<dyn TokenValidator>>,
+}
+
+impl<T> MakeAuthenticator<T> {
+    /// Create a middleware that authenticates API keys against `keys`. Pass an empty
+    /// `InMemoryKeyStore` to accept bearer tokens only.
+    pub fn new(inner: T, keys: impl KeyStore + 'static) -> Self {
+        MakeAuthenticator {
+            inner,
+            keys: vec![Arc::new(keys)],
+            bearer: Vec::new(),
+        }
+    }
+
+    /// Also authenticate API keys against `keys`. Keys are looked up in each store in
+    /// the order they're added.
+    pub fn with_keys(mut self, keys: impl KeyStore + 'static) -> Self {
+        self.keys.push(Arc::new(keys));
+        self
+    }
+
+    /// Also authenticate bearer tokens with `validator`. Tokens are tried against each
+    /// validator in the order they're added.
+    pub fn with_bearer(mut self, validator: impl TokenValidator + 'static) -> Self {
+        self.bearer.push(Arc::new(validator));
+        self
+    }
+}
+
+impl<T, Target> Service<Target> for MakeAuthenticator<T>
+where
+    T: Service<Target>,
+    T::Future: Send + 'static,
+{
+    type Error = T::Error;
+    type Response = Authenticator<T::Response>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        let keys = self.keys.clone();
+        let bearer = self.bearer.clone();
+        let service = self.inner.call(target);
+        Box::pin(async move {
+            Ok(Authenticator { inner: service.await?, keys, bearer })
+        })
+    }
+}
+
+/// A `tower::Layer` that wraps services in `Authenticator`.
+#[derive(Clone)]
+pub struct AuthenticatorLayer {
+    keys: Vec<Arc<dyn KeyStore>>,
+    bearer: Vec<Arc<dyn TokenValidator>>,
+}
+
+impl AuthenticatorLayer {
+    /// Authenticate API keys against `keys`.
+    pub fn new(keys: impl KeyStore + 'static) -> Self {
+        AuthenticatorLayer {
+            keys: vec![Arc::new(keys)],
+            bearer: Vec::new(),
+        }
+    }
+
+    /// Also authenticate API keys against `keys`.
+    pub fn with_keys(mut self, keys: impl KeyStore + 'static) -> Self {
+        self.keys.push(Arc::new(keys));
+        self
+    }
+
+    /// Also authenticate bearer tokens with `validator`.
+    pub fn with_bearer(mut self, validator: impl TokenValidator + 'static) -> Self {
+        self.bearer.push(Arc::new(validator));
+        self
+    }
+}
+
+impl<S> Layer<S> for AuthenticatorLayer {
+    type Service = Authenticator<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authenticator {
+            inner,
+            keys: self.keys.clone(),
+            bearer: self.bearer.clone(),
+        }
+    }
+}
+
+/// Adds the `Authorization` for the request's credentials to its context.
+///
+/// An API key that no `KeyStore` knows leaves the request unauthenticated. A
+/// bearer token that no `TokenValidator` accepts is rejected with a 401, whose
+/// `WWW-Authenticate` header says why. A request that's already authorized, by
+/// middleware further out, is passed on as it is.
+#[derive(Clone)]
+pub struct Authenticator<T> {
+    inner: T,
+    keys: Vec<Arc<dyn KeyStore>>,
+    bearer: Vec<Arc<dyn TokenValidator>>,
+}
+
+impl<T> Authenticator<T> {
+    /// The authorization for a bearer token, from the first validator to accept it. If
+    /// none does, the first validator's reason is reported.
+    fn validate_bearer(&self, token: &str) -> Result<Option<Authorization>, TokenError> {
+        let mut first_error = None;
+        for validator in &self.bearer {
+            match validator.validate(token) {
+                Ok(authorization) => return Ok(Some(authorization)),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T, C, ReqBody> Service<(Request<ReqBody>, C)> for Authenticator<T>
+where
+    C: Has<XSpanIdString> + Has<Option<AuthData>> + Has<Option<Authorization>> + Push<Option<Authorization>>,
+    T: Service<(Request<ReqBody>, C::Result), Response = Response<Body>>,
+{
+    type Response = Response<Body>;
+    type Error = T::Error;
+    type Future = Either<T::Future, Ready<Result<Response<Body>, T::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, (request, context): (Request<ReqBody>, C)) -> Self::Future {
+        let authorization = match Has::<Option<Authorization>>::get(&context) {
+            Some(authorization) => Some(authorization.clone()),
+            None => match Has::<Option<AuthData>>::get(&context) {
+                Some(AuthData::ApiKey(api_key)) => {
+                    let authorization = self.keys.iter().find_map(|keys| keys.authorize(api_key));
+                    if authorization.is_none() {
+                        warn!("Rejecting unknown API key");
+                    }
+                    authorization
+                },
+                Some(AuthData::Bearer(bearer)) => match self.validate_bearer(&bearer.token) {
+                    Ok(authorization) => authorization,
+                    Err(e) => {
+                        let span_id = Has::<XSpanIdString>::get(&context).0.clone();
+                        warn!("Rejecting bearer token - X-Span-ID: {} - {}", span_id, e);
+                        return Either::Right(future::ready(Ok(invalid_token(span_id, &e))));
+                    },
+                },
+                _ => None,
+            },
+        };
+        let context = context.push(authorization);
+        Either::Left(self.inner.call((request, context)))
+    }
+}
+
+/// A 401 response for a rejected bearer token, as RFC 6750 describes.
+fn invalid_token(span_id: String, error: &TokenError) -> Response<Body> {
+    let mut response = Problems::new(None, span_id).response(StatusCode::UNAUTHORIZED, ProblemType::InvalidToken, error.to_string());
+    let challenge = format!("Bearer error=\"invalid_token\", error_description=\"{}\"", error.0.replace('"', "'"));
+    let challenge = HeaderValue::from_str(&challenge)
+        .unwrap_or_else(|_| HeaderValue::from_static("Bearer error=\"invalid_token\""));
+    response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+    response
+}
@@ -0,0 +1,354 @@
+//! Reading request bodies within size limits, either fully buffered or, for JSON arrays,
+//! one element at a time.
+
+use futures::stream::{self, BoxStream, StreamExt};
+use hyper::header::CONTENT_LENGTH;
+use hyper::{Body, HeaderMap};
+use log::warn;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use swagger::ApiError;
+
+use crate::validate::{Validate, Violation};
+use super::router;
+use super::unknown_fields::{self, UnknownFields};
+
+/// The largest request body that an operation accepts unless configured otherwise: 1 MiB.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Maximum request body sizes, in bytes, for all operations and for individual ones.
+///
+/// Bodies whose `Content-Length` exceeds the limit are rejected with a 413 before any of
+/// the body is read, and bodies without one are rejected as soon as the limit is passed.
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    default: Option<usize>,
+    operations: HashMap<String, Option<usize>>,
+}
+
+impl Default for BodyLimits {
+    /// Limit every operation to `DEFAULT_MAX_BODY_SIZE`.
+    fn default() -> Self {
+        BodyLimits::new(DEFAULT_MAX_BODY_SIZE)
+    }
+}
+
+impl BodyLimits {
+    /// Limit every operation to `max` bytes.
+    pub fn new(max: usize) -> Self {
+        BodyLimits {
+            default: Some(max),
+            operations: HashMap::new(),
+        }
+    }
+
+    /// Don't limit request bodies, except for operations given a limit with `operation`.
+    pub fn unlimited() -> Self {
+        BodyLimits {
+            default: None,
+            operations: HashMap::new(),
+        }
+    }
+
+    /// Limit the operation with the given ID, e.g. `CreateUsersWithListInput`, to `max`
+    /// bytes - or don't limit it, if `max` is `None`.
+    ///
+    /// # Panics
+    ///
+    /// If there's no operation with the given ID in `router::ROUTES`.
+    pub fn operation(mut self, operation_id: impl Into<String>, max: Option<usize>) -> Self {
+        let operation_id = operation_id.into();
+        assert!(router::operation(&operation_id).is_some(), "No operation has the ID {}", operation_id);
+        self.operations.insert(operation_id, max);
+        self
+    }
+
+    /// The limit for an operation.
+    pub fn limit(&self, operation_id: &str) -> Option<usize> {
+        self.operations.get(operation_id).copied().unwrap_or(self.default)
+    }
+}
+
+/// A request body that couldn't be read.
+#[derive(Debug)]
+pub(crate) enum BodyError {
+    /// The body is larger than the limit, in bytes
+    TooLarge(usize),
+    /// The connection failed
+    Read(hyper::Error),
+    /// The body isn't well-formed
+    Invalid(String),
+    /// An element of the body violates the schema
+    Violations(Vec<Violation>),
+    /// An element of the body has fields that the schema doesn't define, at these pointers
+    UnknownFields(Vec<String>),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyError::TooLarge(limit) => write!(f, "body exceeds the limit of {} bytes", limit),
+            BodyError::Read(e) => write!(f, "{}", e),
+            BodyError::Invalid(e) => f.write_str(e),
+            BodyError::Violations(violations) => {
+                let violations = violations.iter().map(Violation::to_string).collect::<Vec<_>>();
+                write!(f, "body violates the schema: {}", violations.join(", "))
+            },
+            BodyError::UnknownFields(pointers) => write!(f, "body has unknown fields: {}", pointers.join(", ")),
+        }
+    }
+}
+
+/// Fail early if the body's `Content-Length` is over the limit.
+fn check_content_length(headers: &HeaderMap, limit: Option<usize>) -> Result<(), BodyError> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let length = headers.get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    match length {
+        Some(length) if length > limit as u64 => Err(BodyError::TooLarge(limit)),
+        _ => Ok(()),
+    }
+}
+
+/// Read a whole request body, failing as soon as it's over `limit` bytes.
+pub(crate) async fn read(mut body: Body, headers: &HeaderMap, limit: Option<usize>) -> Result<Vec<u8>, BodyError> {
+    check_content_length(headers, limit)?;
+
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(BodyError::Read)?;
+        if let Some(limit) = limit.filter(|limit| buffer.len() + chunk.len() > *limit) {
+            return Err(BodyError::TooLarge(limit));
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(buffer)
+}
+
+/// What happened while an `Api` implementation consumed a streamed body, for the router
+/// to report once the implementation returns.
+#[derive(Debug, Default)]
+pub(crate) struct StreamOutcome {
+    /// The first error, which ended the stream
+    pub(crate) error: Option<BodyError>,
+    /// Pointers to the fields that deserialization ignored, unless they're to be ignored
+    /// silently
+    pub(crate) unused_elements: Vec<String>,
+}
+
+/// Stream the elements of a JSON array body, deserializing and validating each as it
+/// arrives, and handling unknown fields as `policy` says. Returns `None` for an
+/// empty body.
+///
+/// The first error ends the stream: the implementation sees it as an `ApiError`, and
+/// the error itself is recorded in `outcome`.
+pub(crate) async fn json_array<T>(mut body: Body, headers: &HeaderMap, limit: Option<usize>, policy: UnknownFields, outcome: Arc<Mutex<StreamOutcome>>)
+    -> Result<Option<BoxStream<'static, Result<T, ApiError>>>, BodyError>
+where
+    T: DeserializeOwned + Validate + Send + 'static,
+{
+    check_content_length(headers, limit)?;
+
+    // Wait for the first data, to tell an empty body from an empty array
+    let mut splitter = ArraySplitter::new();
+    loop {
+        match body.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(BodyError::Read)?;
+                if !chunk.is_empty() {
+                    splitter.push(&chunk, limit)?;
+                    break;
+                }
+            },
+            None => return Ok(None),
+        }
+    }
+
+    let state = (body, splitter, 0, false);
+    let elements = stream::unfold(state, move |(mut body, mut splitter, index, done)| {
+        let outcome = outcome.clone();
+        async move {
+            if done {
+                return None;
+            }
+            let result = next_element::<T>(&mut body, &mut splitter, limit, policy, index, &outcome).await;
+            match result {
+                Ok(Some(element)) => Some((Ok(element), (body, splitter, index + 1, false))),
+                Ok(None) => None,
+                Err(e) => {
+                    let message = e.to_string();
+                    let mut outcome = outcome.lock().expect("Stream outcome lock poisoned");
+                    outcome.error.get_or_insert(e);
+                    Some((Err(ApiError(message)), (body, splitter, index, true)))
+                },
+            }
+        }
+    });
+    Ok(Some(elements.boxed()))
+}
+
+async fn next_element<T>(body: &mut Body, splitter: &mut ArraySplitter, limit: Option<usize>, policy: UnknownFields, index: usize, outcome: &Mutex<StreamOutcome>)
+    -> Result<Option<T>, BodyError>
+where
+    T: DeserializeOwned + Validate,
+{
+    loop {
+        if let Some(element) = splitter.next_element()? {
+            let deserializer = &mut serde_json::Deserializer::from_slice(&element);
+            let mut unused_elements = Vec::new();
+            let element: T = serde_ignored::deserialize(deserializer, |path| {
+                unused_elements.push(format!("/{}{}", index, unknown_fields::pointer(&path)));
+            }).map_err(|e| BodyError::Invalid(format!("Element {} of the body is invalid: {}", index, e)))?;
+
+            match policy {
+                UnknownFields::Reject if !unused_elements.is_empty() => return Err(BodyError::UnknownFields(unused_elements)),
+                UnknownFields::Reject | UnknownFields::Ignore => {},
+                UnknownFields::Warn => {
+                    for path in &unused_elements {
+                        warn!("Ignoring unknown field in body: {}", path);
+                    }
+                    outcome.lock().expect("Stream outcome lock poisoned").unused_elements.extend(unused_elements);
+                },
+            }
+
+            let mut violations = Vec::new();
+            element.validate_at(&format!("/{}", index), &mut violations);
+            if !violations.is_empty() {
+                return Err(BodyError::Violations(violations));
+            }
+            return Ok(Some(element));
+        }
+
+        if splitter.finished() {
+            return Ok(None);
+        }
+
+        match body.next().await {
+            Some(chunk) => splitter.push(&chunk.map_err(BodyError::Read)?, limit)?,
+            None => return Err(BodyError::Invalid("Body ended before the end of the array".to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// Before the opening `[`
+    Start,
+    /// Between elements
+    Between,
+    /// Inside an element
+    Element,
+    /// After the closing `]`
+    End,
+}
+
+/// Splits a JSON array arriving in chunks into the text of its elements, without
+/// parsing them.
+#[derive(Debug)]
+struct ArraySplitter {
+    buffer: Vec<u8>,
+    /// Bytes received so far, for the size limit
+    received: usize,
+    /// How far `buffer` has been scanned
+    scanned: usize,
+    position: Position,
+    /// Start of the current element in `buffer`
+    start: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl ArraySplitter {
+    fn new() -> Self {
+        ArraySplitter {
+            buffer: Vec::new(),
+            received: 0,
+            scanned: 0,
+            position: Position::Start,
+            start: 0,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8], limit: Option<usize>) -> Result<(), BodyError> {
+        self.received += chunk.len();
+        if let Some(limit) = limit.filter(|limit| self.received > *limit) {
+            return Err(BodyError::TooLarge(limit));
+        }
+        self.buffer.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    fn finished(&self) -> bool {
+        self.position == Position::End
+    }
+
+    /// The next complete element, if one has arrived.
+    fn next_element(&mut self) -> Result<Option<Vec<u8>>, BodyError> {
+        while self.scanned < self.buffer.len() {
+            let byte = self.buffer[self.scanned];
+            self.scanned += 1;
+
+            match self.position {
+                Position::Start => match byte {
+                    b'[' => self.position = Position::Between,
+                    byte if byte.is_ascii_whitespace() => {},
+                    _ => return Err(BodyError::Invalid("Body is not a JSON array".to_string())),
+                },
+                Position::Between => match byte {
+                    b']' => self.position = Position::End,
+                    b',' => {},
+                    byte if byte.is_ascii_whitespace() => {},
+                    _ => {
+                        self.position = Position::Element;
+                        self.start = self.scanned - 1;
+                        self.scan(byte);
+                    },
+                },
+                Position::Element => {
+                    if !self.in_string && self.depth == 0 && (byte == b',' || byte == b']') {
+                        let element = self.buffer[self.start..self.scanned - 1].to_vec();
+                        self.position = if byte == b']' { Position::End } else { Position::Between };
+                        // Drop what's been consumed, so the buffer only holds one element
+                        self.buffer.drain(..self.scanned);
+                        self.scanned = 0;
+                        return Ok(Some(element));
+                    }
+                    self.scan(byte);
+                },
+                Position::End => if !byte.is_ascii_whitespace() {
+                    return Err(BodyError::Invalid("Unexpected data after the end of the array".to_string()));
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Track strings and nesting within an element.
+    fn scan(&mut self, byte: u8) {
+        if self.in_string {
+            match byte {
+                _ if self.escaped => self.escaped = false,
+                b'\\' => self.escaped = true,
+                b'"' => self.in_string = false,
+                _ => {},
+            }
+            return;
+        }
+        match byte {
+            b'"' => self.in_string = true,
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+}
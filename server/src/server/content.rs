@@ -0,0 +1,113 @@
+//! Request body decoding driven by the request's `Content-Type`, and response
+//! representation selection driven by its `Accept` header.
+
+use hyper::HeaderMap;
+use hyper::header::{ACCEPT, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+
+use crate::form;
+use crate::media::MediaType;
+
+/// Work out which of the media types declared for an operation's request body
+/// the request has been encoded with.
+///
+/// A missing `Content-Type` selects the first declared media type. Anything the
+/// operation doesn't declare is an error, which the router turns into a 415.
+pub(crate) fn request_media_type(headers: &HeaderMap, declared: &[MediaType]) -> Result<MediaType, String> {
+    let content_type = match headers.get(CONTENT_TYPE) {
+        Some(content_type) => content_type,
+        None => return declared.first().copied()
+            .ok_or_else(|| "Operation does not accept a request body".to_string()),
+    };
+
+    let content_type = content_type.to_str()
+        .map_err(|e| format!("Unable to parse Content-Type header as a string: {}", e))?;
+    let media_type = content_type.parse::<MediaType>()?;
+
+    if declared.contains(&media_type) {
+        Ok(media_type)
+    } else {
+        Err(format!("Media type {} is not supported by this operation, expected one of: {}",
+            media_type,
+            declared.iter().map(MediaType::as_str).collect::<Vec<_>>().join(", ")))
+    }
+}
+
+/// Deserialize a request body encoded as `media_type`, reporting the path of every
+/// field that the target type ignored to `callback`.
+pub(crate) fn deserialize_body<T, F>(media_type: MediaType, body: &[u8], callback: F) -> Result<T, String>
+where
+    T: DeserializeOwned,
+    F: FnMut(serde_ignored::Path),
+{
+    match media_type {
+        MediaType::Json => {
+            let deserializer = &mut serde_json::Deserializer::from_slice(body);
+            serde_ignored::deserialize(deserializer, callback).map_err(|e| e.to_string())
+        },
+        MediaType::Xml => {
+            let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(body);
+            serde_ignored::deserialize(deserializer, callback).map_err(|e| e.to_string())
+        },
+        MediaType::FormUrlEncoded => {
+            let deserializer = form::Deserializer::from_bytes(body).map_err(|e| e.to_string())?;
+            serde_ignored::deserialize(deserializer, callback).map_err(|e| e.to_string())
+        },
+    }
+}
+
+/// Pick the representation of a response body from the media types the operation
+/// declares, honouring the q-values and wildcards of the request's `Accept` header.
+///
+/// A missing `Accept` header selects the first offered media type, as does a tie
+/// between equally acceptable ones. Anything else that isn't acceptable is an error,
+/// which the router turns into a 406.
+pub(crate) fn response_media_type(headers: &HeaderMap, offered: &[MediaType]) -> Result<MediaType, String> {
+    let ranges = headers.get_all(ACCEPT).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+        .filter_map(|range| range.parse::<mime::Mime>().ok())
+        .collect::<Vec<_>>();
+
+    if ranges.is_empty() {
+        return offered.first().copied()
+            .ok_or_else(|| "Operation does not produce a response body".to_string());
+    }
+
+    let mut best: Option<(MediaType, f32)> = None;
+    for media_type in offered {
+        let quality = quality(&ranges, *media_type);
+        if quality > 0.0 && best.map_or(true, |(_, best_quality)| quality > best_quality) {
+            best = Some((*media_type, quality));
+        }
+    }
+
+    best.map(|(media_type, _)| media_type)
+        .ok_or_else(|| format!("None of the available representations are acceptable: {}",
+            offered.iter().map(MediaType::as_str).collect::<Vec<_>>().join(", ")))
+}
+
+/// The q-value that the most specific media range matching `media_type` assigns to it,
+/// or 0 if no range matches.
+fn quality(ranges: &[mime::Mime], media_type: MediaType) -> f32 {
+    ranges.iter()
+        .filter_map(|range| {
+            let specificity = if MediaType::from_mime(range) == Some(media_type) {
+                2
+            } else if range.type_() == mime::APPLICATION && range.subtype() == mime::STAR {
+                1
+            } else if range.type_() == mime::STAR && range.subtype() == mime::STAR {
+                0
+            } else {
+                return None;
+            };
+            let quality = range.get_param("q")
+                .and_then(|q| q.as_str().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((specificity, quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, quality)| quality)
+}
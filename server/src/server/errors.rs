@@ -0,0 +1,88 @@
+//! Mapping of errors returned by an `Api` implementation onto HTTP responses.
+
+use hyper::{HeaderMap, StatusCode};
+use hyper::header::{HeaderName, HeaderValue};
+use swagger::ApiError;
+
+use crate::problem::{Problem, ProblemType};
+
+/// How an error returned by an `Api` implementation is reported to the client.
+#[derive(Debug, Clone)]
+pub struct ErrorResponse {
+    /// Status code of the response
+    pub status: StatusCode,
+
+    /// Additional headers to send, such as `Retry-After`
+    pub headers: HeaderMap,
+
+    /// Problem details sent as the body. The router fills in `status`, `instance` and
+    /// `operationId`.
+    pub problem: Problem,
+}
+
+impl ErrorResponse {
+    /// A response with the given status code, whose problem details carry `detail`.
+    ///
+    /// The problem has no type beyond the status code, so its `type` is `about:blank`.
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
+        ErrorResponse {
+            status,
+            headers: HeaderMap::new(),
+            problem: Problem {
+                type_uri: "about:blank".to_string(),
+                title: status.canonical_reason().unwrap_or_default().to_string(),
+                status: status.as_u16(),
+                detail: Some(detail.into()),
+                instance: None,
+                operation_id: None,
+                violations: Vec::new(),
+            },
+        }
+    }
+
+    /// Identify the kind of problem with a `type` URI and its title.
+    pub fn with_problem_type(mut self, type_uri: impl Into<String>, title: impl Into<String>) -> Self {
+        self.problem.type_uri = type_uri.into();
+        self.problem.title = title.into();
+        self
+    }
+
+    /// Send an additional header.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+}
+
+/// Decides how errors returned by an `Api` implementation are reported to the client.
+///
+/// This lets implementations signal, for example, a 409, 422 or 503 without adding
+/// response variants. Closures taking the operation id and the error can be used directly.
+pub trait ApiErrorMapper: Send + Sync {
+    /// Map `error`, returned by the implementation of `operation_id`, to a response.
+    fn map_error(&self, operation_id: &str, error: &ApiError) -> ErrorResponse;
+}
+
+impl<F> ApiErrorMapper for F where
+    F: Fn(&str, &ApiError) -> ErrorResponse + Send + Sync
+{
+    fn map_error(&self, operation_id: &str, error: &ApiError) -> ErrorResponse {
+        self(operation_id, error)
+    }
+}
+
+/// The default `ApiErrorMapper`, which reports every error as a 500 without exposing
+/// its message to the client.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InternalErrorMapper;
+
+impl ApiErrorMapper for InternalErrorMapper {
+    fn map_error(&self, _operation_id: &str, _error: &ApiError) -> ErrorResponse {
+        let status = StatusCode::INTERNAL_SERVER_ERROR;
+        ErrorResponse {
+            status,
+            headers: HeaderMap::new(),
+            problem: Problem::new(ProblemType::InternalError, status.as_u16(), "An internal error occurred"),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Validating JWT bearer tokens, signed with HS256, RS256 or ES256, into an
+//! `Authorization` whose scopes come from the token's `scope` claim.
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use swagger::auth::{Authorization, Scopes};
+
+use super::auth::{TokenError, TokenValidator};
+
+/// A key that couldn't be loaded.
+#[derive(Debug)]
+pub enum JwtKeyError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file isn't a PEM key of the expected type
+    Pem(jsonwebtoken::errors::Error),
+    /// The file isn't a JWK set
+    Json(serde_json::Error),
+    /// A JWK is for an algorithm other than HS256, RS256 or ES256
+    Unsupported(String),
+}
+
+impl fmt::Display for JwtKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtKeyError::Io(e) => write!(f, "Couldn't read key: {}", e),
+            JwtKeyError::Pem(e) => write!(f, "Couldn't parse key: {}", e),
+            JwtKeyError::Json(e) => write!(f, "Couldn't parse JWK set: {}", e),
+            JwtKeyError::Unsupported(e) => write!(f, "Unsupported key: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JwtKeyError {}
+
+impl From<io::Error> for JwtKeyError {
+    fn from(e: io::Error) -> Self {
+        JwtKeyError::Io(e)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for JwtKeyError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        JwtKeyError::Pem(e)
+    }
+}
+
+impl From<serde_json::Error> for JwtKeyError {
+    fn from(e: serde_json::Error) -> Self {
+        JwtKeyError::Json(e)
+    }
+}
+
+/// A key that verifies token signatures made with one algorithm.
+#[derive(Clone)]
+pub struct JwtKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    key_id: Option<String>,
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl JwtKey {
+    /// An HS256 shared secret.
+    pub fn hs256(secret: impl AsRef<[u8]>) -> Self {
+        JwtKey {
+            algorithm: Algorithm::HS256,
+            key: DecodingKey::from_secret(secret.as_ref()),
+            key_id: None,
+        }
+    }
+
+    /// An RS256 public key, from a PEM file.
+    pub fn rs256_pem(path: impl AsRef<Path>) -> Result<Self, JwtKeyError> {
+        Ok(JwtKey {
+            algorithm: Algorithm::RS256,
+            key: DecodingKey::from_rsa_pem(&fs::read(path)?)?,
+            key_id: None,
+        })
+    }
+
+    /// An ES256 (P-256) public key, from a PEM file.
+    pub fn es256_pem(path: impl AsRef<Path>) -> Result<Self, JwtKeyError> {
+        Ok(JwtKey {
+            algorithm: Algorithm::ES256,
+            key: DecodingKey::from_ec_pem(&fs::read(path)?)?,
+            key_id: None,
+        })
+    }
+
+    /// The keys in a JWK set file, each used for the algorithm its type implies: `oct`
+    /// keys for HS256, `RSA` keys for RS256 and P-256 `EC` keys for ES256.
+    pub fn jwk_set(path: impl AsRef<Path>) -> Result<Vec<Self>, JwtKeyError> {
+        let jwk_set: JwkSet = serde_json::from_slice(&fs::read(path)?)?;
+        jwk_set.keys.iter()
+            .map(|jwk| {
+                let algorithm = match jwk.algorithm {
+                    AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+                    AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                    AlgorithmParameters::EllipticCurve(ref parameters) if parameters.curve == EllipticCurve::P256 => Algorithm::ES256,
+                    _ => return Err(JwtKeyError::Unsupported(format!("JWK {} isn't an oct, RSA or P-256 EC key",
+                        jwk.common.key_id.as_deref().unwrap_or("without a kid")))),
+                };
+                let key = match jwk.algorithm {
+                    AlgorithmParameters::OctetKey(ref parameters) => DecodingKey::from_base64_secret(&standard_base64(&parameters.value))?,
+                    _ => DecodingKey::from_jwk(jwk)?,
+                };
+                Ok(JwtKey {
+                    algorithm,
+                    key,
+                    key_id: jwk.common.key_id.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Only use this key for tokens whose header names it with `kid`.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+}
+
+/// A JWK's unpadded base64url value, as RFC 7518 encodes `oct` keys, in the padded
+/// standard base64 that `DecodingKey::from_jwk` would wrongly expect of them.
+fn standard_base64(value: &str) -> String {
+    let mut value: String = value.chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    let padding = (4 - value.len() % 4) % 4;
+    value.push_str(&"==="[..padding]);
+    value
+}
+
+/// The claims that make up an `Authorization`.
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    iss: Option<String>,
+    /// Space-separated scopes, as in RFC 8693
+    #[serde(default)]
+    scope: String,
+}
+
+/// Validates JWT bearer tokens against a set of keys.
+///
+/// A token must be signed by one of the keys, using that key's algorithm, and have `exp`
+/// and `sub` claims. It's rejected once `exp` has passed and before any `nbf`, and, if
+/// an issuer or audience is configured, unless its `iss` or `aud` matches.
+#[derive(Debug, Clone, Default)]
+pub struct JwtValidator {
+    keys: Vec<JwtKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway: u64,
+}
+
+impl JwtValidator {
+    /// Accept tokens signed by `key`.
+    pub fn new(key: JwtKey) -> Self {
+        JwtValidator::default().with_key(key)
+    }
+
+    /// Also accept tokens signed by `key`, e.g. while keys are being rotated.
+    pub fn with_key(mut self, key: JwtKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Also accept tokens signed by any of `keys`.
+    pub fn with_keys(mut self, keys: impl IntoIterator<Item = JwtKey>) -> Self {
+        self.keys.extend(keys);
+        self
+    }
+
+    /// Only accept tokens issued by `issuer`.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Only accept tokens meant for `audience`.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Allow for clock skew of up to `seconds` when checking `exp` and `nbf`.
+    pub fn with_leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.leeway;
+        validation.validate_nbf = true;
+        let mut required_claims = vec!["exp", "sub"];
+        if let Some(ref issuer) = self.issuer {
+            validation.set_issuer(&[issuer]);
+            required_claims.push("iss");
+        }
+        if let Some(ref audience) = self.audience {
+            validation.set_audience(&[audience]);
+            required_claims.push("aud");
+        }
+        validation.set_required_spec_claims(&required_claims);
+        validation
+    }
+}
+
+impl TokenValidator for JwtValidator {
+    fn validate(&self, token: &str) -> Result<Authorization, TokenError> {
+        let header = jsonwebtoken::decode_header(token).map_err(describe)?;
+
+        // Only keys for the token's algorithm are tried, so that a token can't pick how
+        // its signature is checked
+        let keys = self.keys.iter()
+            .filter(|key| key.algorithm == header.alg)
+            .filter(|key| match (&header.kid, &key.key_id) {
+                (Some(kid), Some(key_id)) => kid == key_id,
+                _ => true,
+            });
+
+        let mut error = TokenError(format!("No key accepts {:?} tokens", header.alg));
+        for key in keys {
+            match jsonwebtoken::decode::<Claims>(token, &key.key, &self.validation(key.algorithm)) {
+                Ok(token) => return Ok(Authorization {
+                    subject: token.claims.sub,
+                    scopes: Scopes::Some(token.claims.scope.split_whitespace().map(str::to_string).collect()),
+                    issuer: token.claims.iss,
+                }),
+                // Another key may have signed it
+                Err(e) if matches!(e.kind(), ErrorKind::InvalidSignature) => error = describe(e),
+                Err(e) => return Err(describe(e)),
+            }
+        }
+        Err(error)
+    }
+}
+
+/// Say why a token was rejected, without echoing it.
+fn describe(error: jsonwebtoken::errors::Error) -> TokenError {
+    let description = match error.kind() {
+        ErrorKind::ExpiredSignature => "Token has expired".to_string(),
+        ErrorKind::ImmatureSignature => "Token is not valid yet".to_string(),
+        ErrorKind::InvalidIssuer => "Token has the wrong issuer".to_string(),
+        ErrorKind::InvalidAudience => "Token has the wrong audience".to_string(),
+        ErrorKind::InvalidSignature => "Token signature is invalid".to_string(),
+        ErrorKind::MissingRequiredClaim(claim) => format!("Token has no {} claim", claim),
+        _ => format!("Token is malformed: {}", error),
+    };
+    TokenError(description)
+}
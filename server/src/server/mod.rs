@@ -1,26 +1,68 @@
 use futures::{future, future::BoxFuture, Stream, stream, future::FutureExt, stream::TryStreamExt};
 use hyper::{Request, Response, StatusCode, Body, HeaderMap};
-use hyper::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use hyper::header::{HeaderName, HeaderValue, ALLOW, CONTENT_LENGTH, CONTENT_TYPE};
 use log::warn;
 #[allow(unused_imports)]
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::future::Future;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use swagger::{ApiError, BodyExt, Has, RequestParser, XSpanIdString};
 pub use swagger::auth::Authorization;
 use swagger::auth::Scopes;
-use url::form_urlencoded;
+use tower::ServiceBuilder;
+use tower::layer::util::{Identity, Stack};
 
 #[allow(unused_imports)]
 use crate::models;
+use crate::cors::{CorsConfig, CorsLayer};
 use crate::header;
+use crate::media::MediaType;
+use crate::problem::ProblemType;
+use crate::query::{QueryParams, Style};
+use crate::validate::{self, Validate};
 
 pub use crate::context;
 
+mod adapter;
+pub mod auth;
+pub mod body;
+mod content;
+mod errors;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+mod problem;
+pub mod router;
+pub mod session;
+pub mod unknown_fields;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+pub use adapter::ApiService;
+pub use auth::{FileKeyStore, InMemoryKeyStore, KeyStore, MakeAuthenticator, TokenError, TokenValidator};
+pub use body::BodyLimits;
+pub use session::{FileSessionStore, InMemorySessionStore, SessionStore, Sessions};
+pub use unknown_fields::{UnknownFields, UnknownFieldsPolicy};
+pub use errors::{ApiErrorMapper, ErrorResponse, InternalErrorMapper};
+use problem::Problems;
+use router::PathRouter;
+
 type ServiceFuture = BoxFuture<'static, Result<Response<Body>, crate::ServiceError>>;
 
+/// The crate's request middleware, for use with `tower::ServiceBuilder` in front of a
+/// `Service`. It builds a context of type `A` from each request, then handles CORS as
+/// `cors` describes.
+///
+/// Layers that work on plain requests, such as those from `tower-http`, go before it;
+/// layers that work on `(Request, Context)` pairs, such as authenticators, go after it.
+pub fn middleware<A>(cors: CorsConfig) -> ServiceBuilder<Stack<CorsLayer, Stack<context::AddContextLayer<A>, Identity>>> {
+    ServiceBuilder::new()
+        .layer(context::AddContextLayer::new())
+        .layer(CorsLayer::new(cors))
+}
+
 use crate::{Api,
      AddPetResponse,
      DeletePetResponse,
@@ -44,61 +86,34 @@ use crate::{Api,
 
 mod paths {
     use lazy_static::lazy_static;
+    use std::sync::Arc;
+
+    use super::router::PathRouter;
 
     lazy_static! {
-        pub static ref GLOBAL_REGEX_SET: regex::RegexSet = regex::RegexSet::new(vec![
-            r"^/api/v3/pet$",
-            r"^/api/v3/pet/findByStatus$",
-            r"^/api/v3/pet/findByTags$",
-            r"^/api/v3/pet/(?P<petId>[^/?#]*)$",
-            r"^/api/v3/store/inventory$",
-            r"^/api/v3/store/order$",
-            r"^/api/v3/store/order/(?P<orderId>[^/?#]*)$",
-            r"^/api/v3/user$",
-            r"^/api/v3/user/createWithList$",
-            r"^/api/v3/user/login$",
-            r"^/api/v3/user/logout$",
-            r"^/api/v3/user/(?P<username>[^/?#]*)$"
-        ])
-        .expect("Unable to create global regex set");
-    }
-    pub(crate) static ID_PET: usize = 0;
-    pub(crate) static ID_PET_FINDBYSTATUS: usize = 1;
-    pub(crate) static ID_PET_FINDBYTAGS: usize = 2;
-    pub(crate) static ID_PET_PETID: usize = 3;
-    lazy_static! {
-        pub static ref REGEX_PET_PETID: regex::Regex =
-            #[allow(clippy::invalid_regex)]
-            regex::Regex::new(r"^/api/v3/pet/(?P<petId>[^/?#]*)$")
-                .expect("Unable to create regex for PET_PETID");
-    }
-    pub(crate) static ID_STORE_INVENTORY: usize = 4;
-    pub(crate) static ID_STORE_ORDER: usize = 5;
-    pub(crate) static ID_STORE_ORDER_ORDERID: usize = 6;
-    lazy_static! {
-        pub static ref REGEX_STORE_ORDER_ORDERID: regex::Regex =
-            #[allow(clippy::invalid_regex)]
-            regex::Regex::new(r"^/api/v3/store/order/(?P<orderId>[^/?#]*)$")
-                .expect("Unable to create regex for STORE_ORDER_ORDERID");
-    }
-    pub(crate) static ID_USER: usize = 7;
-    pub(crate) static ID_USER_CREATEWITHLIST: usize = 8;
-    pub(crate) static ID_USER_LOGIN: usize = 9;
-    pub(crate) static ID_USER_LOGOUT: usize = 10;
-    pub(crate) static ID_USER_USERNAME: usize = 11;
-    lazy_static! {
-        pub static ref REGEX_USER_USERNAME: regex::Regex =
-            #[allow(clippy::invalid_regex)]
-            regex::Regex::new(r"^/api/v3/user/(?P<username>[^/?#]*)$")
-                .expect("Unable to create regex for USER_USERNAME");
+        /// Router for the API mounted at `BASE_PATH`
+        pub static ref ROUTER: Arc<PathRouter> = Arc::new(PathRouter::for_routes(crate::BASE_PATH));
     }
 }
 
+/// Convert the status code that an implementation chose for a default response,
+/// falling back to a 500 if it isn't a valid status code.
+fn default_status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or_else(|_| {
+        warn!("Invalid status code {} for default response", status);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
 pub struct MakeService<T, C> where
     T: Api<C> + Clone + Send + 'static,
     C: Has<XSpanIdString> + Has<Option<Authorization>> + Send + Sync + 'static
 {
     api_impl: T,
+    error_mapper: Arc<dyn ApiErrorMapper>,
+    router: Arc<PathRouter>,
+    body_limits: Arc<BodyLimits>,
+    unknown_fields: Arc<UnknownFieldsPolicy>,
     marker: PhantomData<C>,
 }
 
@@ -109,9 +124,46 @@ impl<T, C> MakeService<T, C> where
     pub fn new(api_impl: T) -> Self {
         MakeService {
             api_impl,
+            error_mapper: Arc::new(InternalErrorMapper),
+            router: paths::ROUTER.clone(),
+            body_limits: Arc::new(BodyLimits::default()),
+            unknown_fields: Arc::new(UnknownFieldsPolicy::default()),
             marker: PhantomData
         }
     }
+
+    /// Serve the API below `base_path`, e.g. `/petstore/v3`, rather than below `BASE_PATH`.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.router = Arc::new(PathRouter::for_routes(base_path));
+        self
+    }
+
+    /// The operation that a request is routed to, below the configured base path.
+    pub fn operation_id<B>(&self, request: &Request<B>) -> Option<&'static str> {
+        router::route(request.method(), &self.router.matches(request.uri().path()))
+            .map(|route| route.operation_id)
+    }
+
+    /// Report errors returned by the `Api` implementation as `error_mapper` decides,
+    /// rather than as a 500.
+    pub fn with_error_mapper(mut self, error_mapper: impl ApiErrorMapper + 'static) -> Self {
+        self.error_mapper = Arc::new(error_mapper);
+        self
+    }
+
+    /// Limit the size of request bodies as `body_limits` describes, rather than to
+    /// `body::DEFAULT_MAX_BODY_SIZE`.
+    pub fn with_body_limits(mut self, body_limits: BodyLimits) -> Self {
+        self.body_limits = Arc::new(body_limits);
+        self
+    }
+
+    /// Handle request body fields that the schema doesn't define as `unknown_fields`
+    /// says, rather than warning about them.
+    pub fn with_unknown_fields(mut self, unknown_fields: UnknownFieldsPolicy) -> Self {
+        self.unknown_fields = Arc::new(unknown_fields);
+        self
+    }
 }
 
 impl<T, C, Target> hyper::service::Service<Target> for MakeService<T, C> where
@@ -127,18 +179,38 @@ impl<T, C, Target> hyper::service::Service<Target> for MakeService<T, C> where
     }
 
     fn call(&mut self, target: Target) -> Self::Future {
-        futures::future::ok(Service::new(
-            self.api_impl.clone(),
-        ))
+        future::ok(Service {
+            api_impl: self.api_impl.clone(),
+            error_mapper: self.error_mapper.clone(),
+            router: self.router.clone(),
+            body_limits: self.body_limits.clone(),
+            unknown_fields: self.unknown_fields.clone(),
+            marker: PhantomData,
+        })
     }
 }
 
-fn method_not_allowed() -> Result<Response<Body>, crate::ServiceError> {
-    Ok(
-        Response::builder().status(StatusCode::METHOD_NOT_ALLOWED)
-            .body(Body::empty())
-            .expect("Unable to create Method Not Allowed response")
-    )
+/// The `Allow` header for a path template.
+fn allow(template: &str) -> HeaderValue {
+    let methods = router::allowed_methods(template).iter()
+        .map(hyper::Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::from_str(&methods).expect("Unable to create Allow header")
+}
+
+fn method_not_allowed(problems: &Problems, method: &hyper::Method, path: &str, template: &str) -> Result<Response<Body>, crate::ServiceError> {
+    let mut response = problems.response(StatusCode::METHOD_NOT_ALLOWED, ProblemType::MethodNotAllowed, format!("Method {} is not allowed for path {}", method, path));
+    response.headers_mut().insert(ALLOW, allow(template));
+    Ok(response)
+}
+
+/// Answer an OPTIONS request with the methods allowed on the path.
+fn options(template: &str) -> Result<Response<Body>, crate::ServiceError> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    response.headers_mut().insert(ALLOW, allow(template));
+    Ok(response)
 }
 
 pub struct Service<T, C> where
@@ -146,6 +218,10 @@ pub struct Service<T, C> where
     C: Has<XSpanIdString> + Has<Option<Authorization>> + Send + Sync + 'static
 {
     api_impl: T,
+    error_mapper: Arc<dyn ApiErrorMapper>,
+    router: Arc<PathRouter>,
+    body_limits: Arc<BodyLimits>,
+    unknown_fields: Arc<UnknownFieldsPolicy>,
     marker: PhantomData<C>,
 }
 
@@ -156,9 +232,46 @@ impl<T, C> Service<T, C> where
     pub fn new(api_impl: T) -> Self {
         Service {
             api_impl,
+            error_mapper: Arc::new(InternalErrorMapper),
+            router: paths::ROUTER.clone(),
+            body_limits: Arc::new(BodyLimits::default()),
+            unknown_fields: Arc::new(UnknownFieldsPolicy::default()),
             marker: PhantomData
         }
     }
+
+    /// Serve the API below `base_path`, e.g. `/petstore/v3`, rather than below `BASE_PATH`.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.router = Arc::new(PathRouter::for_routes(base_path));
+        self
+    }
+
+    /// The operation that a request is routed to, below the configured base path.
+    pub fn operation_id<B>(&self, request: &Request<B>) -> Option<&'static str> {
+        router::route(request.method(), &self.router.matches(request.uri().path()))
+            .map(|route| route.operation_id)
+    }
+
+    /// Report errors returned by the `Api` implementation as `error_mapper` decides,
+    /// rather than as a 500.
+    pub fn with_error_mapper(mut self, error_mapper: impl ApiErrorMapper + 'static) -> Self {
+        self.error_mapper = Arc::new(error_mapper);
+        self
+    }
+
+    /// Limit the size of request bodies as `body_limits` describes, rather than to
+    /// `body::DEFAULT_MAX_BODY_SIZE`.
+    pub fn with_body_limits(mut self, body_limits: BodyLimits) -> Self {
+        self.body_limits = Arc::new(body_limits);
+        self
+    }
+
+    /// Handle request body fields that the schema doesn't define as `unknown_fields`
+    /// says, rather than warning about them.
+    pub fn with_unknown_fields(mut self, unknown_fields: UnknownFieldsPolicy) -> Self {
+        self.unknown_fields = Arc::new(unknown_fields);
+        self
+    }
 }
 
 impl<T, C> Clone for Service<T, C> where
@@ -168,6 +281,10 @@ impl<T, C> Clone for Service<T, C> where
     fn clone(&self) -> Self {
         Service {
             api_impl: self.api_impl.clone(),
+            error_mapper: self.error_mapper.clone(),
+            router: self.router.clone(),
+            body_limits: self.body_limits.clone(),
+            unknown_fields: self.unknown_fields.clone(),
             marker: self.marker,
         }
     }
@@ -185,77 +302,91 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
         self.api_impl.poll_ready(cx)
     }
 
-    fn call(&mut self, req: (Request<Body>, C)) -> Self::Future { async fn run<T, C>(mut api_impl: T, req: (Request<Body>, C)) -> Result<Response<Body>, crate::ServiceError> where
-        T: Api<C> + Clone + Send + 'static,
+    fn call(&mut self, req: (Request<Body>, C)) -> Self::Future { async fn run<T, C>(mut api_impl: T, error_mapper: Arc<dyn ApiErrorMapper>, router: Arc<PathRouter>, body_limits: Arc<BodyLimits>, unknown_fields: Arc<UnknownFieldsPolicy>, req: (Request<Body>, C)) -> Result<Response<Body>, crate::ServiceError> where
+        T: Api<C> + Clone + Send + Sync + 'static,
         C: Has<XSpanIdString> + Has<Option<Authorization>> + Send + Sync + 'static
     {
         let (request, context) = req;
         let (parts, body) = request.into_parts();
         let (method, uri, headers) = (parts.method, parts.uri, parts.headers);
-        let path = paths::GLOBAL_REGEX_SET.matches(uri.path());
+        let path = router.matches(uri.path());
+        let route = router::route(&method, &path);
+        let problems = Problems::new(
+            route.map(|route| route.operation_id),
+            (&context as &dyn Has<XSpanIdString>).get().0.clone());
+
+        // Authorization - scopes are only checked for credentials that carry them
+        if let (Some(route), Some(authorization)) = (route, (&context as &dyn Has<Option<Authorization>>).get()) {
+            if let Scopes::Some(ref scopes) = authorization.scopes {
+                let missing_scopes = route.scopes.iter()
+                    .filter(|scope| !scopes.contains(**scope))
+                    .collect::<Vec<_>>();
+                if !missing_scopes.is_empty() {
+                    return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::InsufficientScope, missing_scopes.iter().fold(
+                        "Insufficient authorization, missing scopes".to_string(),
+                        |s, scope| format!("{} {}", s, scope))));
+                }
+            }
+        }
 
-        match method {
+        match route.map(|route| route.operation_id) {
 
             // AddPet - POST /pet
-            hyper::Method::POST if path.matched(paths::ID_PET) => {
+            Some("AddPet") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                 // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
+                // values, rather than causing a 400 response). Handle any unused fields as the
+                // operation's unknown fields policy says.
+                let content_type = match content::request_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                    MediaType::FormUrlEncoded,
+                ]) {
+                    Ok(content_type) => content_type,
+                    Err(e) => return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter Pet - {}", e))),
+                };
+                let result = body::read(body, &headers, body_limits.limit("AddPet")).await;
                 match result {
                             Ok(body) => {
                                 let mut unused_elements = Vec::new();
                                 let param_pet: Option<models::Pet> = if !body.is_empty() {
-                                    let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
+                                    match content::deserialize_body::<models::Pet, _>(content_type, &body, |path| {
+                                            unused_elements.push(unknown_fields::pointer(&path));
                                     }) {
-                                        Ok(param_pet) => param_pet,
-                                        Err(e) => return Ok(Response::builder()
-                                                        .status(StatusCode::BAD_REQUEST)
-                                                        .body(Body::from(format!("Couldn't parse body parameter Pet - doesn't match schema: {}", e)))
-                                                        .expect("Unable to create Bad Request response for invalid body parameter Pet due to schema")),
+                                        Ok(param_pet) => Some(param_pet),
+                                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't parse body parameter Pet - doesn't match schema: {}", e))),
                                     }
                                 } else {
                                     None
                                 };
+                                match unknown_fields.policy("AddPet") {
+                                    UnknownFields::Reject if !unused_elements.is_empty() => return Ok(problems.unknown_fields("Body parameter Pet has unknown fields", unused_elements)),
+                                    UnknownFields::Reject => {},
+                                    UnknownFields::Warn => for path in &unused_elements {
+                                        warn!("Ignoring unknown field in body: {}", path);
+                                    },
+                                    UnknownFields::Ignore => unused_elements.clear(),
+                                }
+                                if let Err(violations) = param_pet.validate() {
+                                    return Ok(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter Pet violates the schema", violations));
+                                }
                                 let param_pet = match param_pet {
                                     Some(param_pet) => param_pet,
-                                    None => return Ok(Response::builder()
-                                                        .status(StatusCode::BAD_REQUEST)
-                                                        .body(Body::from("Missing required body parameter Pet"))
-                                                        .expect("Unable to create Bad Request response for missing body parameter Pet")),
+                                    None => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::MissingBody, "Missing required body parameter Pet")),
                                 };
 
                                 let result = api_impl.add_pet(
@@ -283,87 +414,57 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for ADD_PET_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 AddPetResponse::InvalidInput
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(405).expect("Unable to turn 405 into a StatusCode");
                                                 },
+                                                AddPetResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter Pet: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter Pet")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter Pet exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter Pet: {}", e))),
                         }
             },
 
             // DeletePet - DELETE /pet/{petId}
-            hyper::Method::DELETE if path.matched(paths::ID_PET_PETID) => {
+            Some("DeletePet") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_PET_PETID
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE PET_PETID in set but failed match against \"{}\"", path, paths::REGEX_PET_PETID.as_str())
-                    );
-
-                let param_pet_id = match percent_encoding::percent_decode(path_params["petId"].as_bytes()).decode_utf8() {
+                let param_pet_id = match percent_encoding::percent_decode(path.param("petId").as_bytes()).decode_utf8() {
                     Ok(param_pet_id) => match param_pet_id.parse::<i64>() {
                         Ok(param_pet_id) => param_pet_id,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter petId: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter petId: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["petId"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("petId"))))
                 };
+                if let Err(violation) = validate::minimum("/petId", param_pet_id, 0) {
+                    return Ok(problems.violations(StatusCode::BAD_REQUEST, "Path parameter petId violates the schema", vec![violation]));
+                }
 
                 // Header parameters
                 let param_api_key = headers.get(HeaderName::from_static("api_key"));
@@ -373,10 +474,7 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                         Ok(result) =>
                             Some(result.0),
                         Err(err) => {
-                            return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Invalid header api_key - {}", err)))
-                                        .expect("Unable to create Bad Request response for invalid header api_key"));
+                            return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Invalid header api_key - {}", err)));
 
                         },
                     },
@@ -398,16 +496,23 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
 
                                         match result {
                                             Ok(rsp) => match rsp {
+                                                DeletePetResponse::PetDeleted
+                                                => {
+                                                    *response.status_mut() = StatusCode::from_u16(204).expect("Unable to turn 204 into a StatusCode");
+                                                },
                                                 DeletePetResponse::InvalidPetValue
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(400).expect("Unable to turn 400 into a StatusCode");
                                                 },
+                                                DeletePetResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -415,57 +520,38 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // FindPetsByStatus - GET /pet/findByStatus
-            hyper::Method::GET if path.matched(paths::ID_PET_FINDBYSTATUS) => {
+            Some("FindPetsByStatus") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
                 // Query parameters (note that non-required or collection query parameters will ignore garbage values, rather than causing a 400 response)
-                let query_params = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()).collect::<Vec<_>>();
-                let param_status = query_params.iter().filter(|e| e.0 == "status").map(|e| e.1.to_owned())
-                    .next();
+                let query_params = QueryParams::parse(uri.query().unwrap_or_default());
+                let param_status = query_params.get("status");
                 let param_status = match param_status {
                     Some(param_status) => {
                         let param_status =
-                            <String as std::str::FromStr>::from_str
-                                (&param_status);
+                            <models::PetStatus as std::str::FromStr>::from_str
+                                (param_status);
                         match param_status {
                             Ok(param_status) => Some(param_status),
-                            Err(e) => return Ok(Response::builder()
-                                .status(StatusCode::BAD_REQUEST)
-                                .body(Body::from(format!("Couldn't parse query parameter status - doesn't match schema: {}", e)))
-                                .expect("Unable to create Bad Request response for invalid query parameter status")),
+                            Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter status - doesn't match schema: {}", e))),
                         }
                     },
                     None => None,
                 };
 
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                                 let result = api_impl.find_pets_by_status(
                                             param_status,
                                         &context
@@ -484,21 +570,27 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for FIND_PETS_BY_STATUS_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => format!("<pets>{}</pets>", body.iter().map(models::Pet::as_xml).collect::<String>()),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 FindPetsByStatusResponse::InvalidStatusValue
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(400).expect("Unable to turn 400 into a StatusCode");
                                                 },
+                                                FindPetsByStatusResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -506,46 +598,28 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // FindPetsByTags - GET /pet/findByTags
-            hyper::Method::GET if path.matched(paths::ID_PET_FINDBYTAGS) => {
+            Some("FindPetsByTags") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
-                // Query parameters (note that non-required or collection query parameters will ignore garbage values, rather than causing a 400 response)
-                let query_params = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()).collect::<Vec<_>>();
-                let param_tags = query_params.iter().filter(|e| e.0 == "tags").map(|e| e.1.to_owned())
-                    .filter_map(|param_tags| param_tags.parse().ok())
-                    .collect::<Vec<_>>();
-                let param_tags = if !param_tags.is_empty() {
-                    Some(param_tags)
-                } else {
-                    None
+                // Query parameters
+                let query_params = QueryParams::parse(uri.query().unwrap_or_default());
+                // Accept tags=a&tags=b, as the definition says, and tags=a,b. No tags - or tags= - is no filter.
+                let param_tags = match query_params.parsed_array::<String>("tags", Style::Form, true) {
+                    Ok(param_tags) => param_tags.filter(|param_tags| !param_tags.is_empty()),
+                    Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter tags - doesn't match schema: {}", e))),
+                };
+
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
                 };
 
                                 let result = api_impl.find_pets_by_tags(
@@ -566,21 +640,27 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for FIND_PETS_BY_TAGS_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => format!("<pets>{}</pets>", body.iter().map(models::Pet::as_xml).collect::<String>()),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 FindPetsByTagsResponse::InvalidTagValue
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(400).expect("Unable to turn 400 into a StatusCode");
                                                 },
+                                                FindPetsByTagsResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -588,58 +668,32 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // GetPetById - GET /pet/{petId}
-            hyper::Method::GET if path.matched(paths::ID_PET_PETID) => {
+            Some("GetPetById") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_PET_PETID
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE PET_PETID in set but failed match against \"{}\"", path, paths::REGEX_PET_PETID.as_str())
-                    );
-
-                let param_pet_id = match percent_encoding::percent_decode(path_params["petId"].as_bytes()).decode_utf8() {
+                let param_pet_id = match percent_encoding::percent_decode(path.param("petId").as_bytes()).decode_utf8() {
                     Ok(param_pet_id) => match param_pet_id.parse::<i64>() {
                         Ok(param_pet_id) => param_pet_id,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter petId: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter petId: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["petId"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("petId"))))
+                };
+                if let Err(violation) = validate::minimum("/petId", param_pet_id, 0) {
+                    return Ok(problems.violations(StatusCode::BAD_REQUEST, "Path parameter petId violates the schema", vec![violation]));
+                }
+
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
                 };
 
                                 let result = api_impl.get_pet_by_id(
@@ -660,9 +714,12 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for GET_PET_BY_ID_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 GetPetByIdResponse::InvalidIDSupplied
@@ -673,12 +730,15 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(404).expect("Unable to turn 404 into a StatusCode");
                                                 },
+                                                GetPetByIdResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -686,65 +746,61 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // UpdatePet - PUT /pet
-            hyper::Method::PUT if path.matched(paths::ID_PET) => {
+            Some("UpdatePet") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                 // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
+                // values, rather than causing a 400 response). Handle any unused fields as the
+                // operation's unknown fields policy says.
+                let content_type = match content::request_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                    MediaType::FormUrlEncoded,
+                ]) {
+                    Ok(content_type) => content_type,
+                    Err(e) => return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter Pet - {}", e))),
+                };
+                let result = body::read(body, &headers, body_limits.limit("UpdatePet")).await;
                 match result {
                             Ok(body) => {
                                 let mut unused_elements = Vec::new();
                                 let param_pet: Option<models::Pet> = if !body.is_empty() {
-                                    let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
+                                    match content::deserialize_body::<models::Pet, _>(content_type, &body, |path| {
+                                            unused_elements.push(unknown_fields::pointer(&path));
                                     }) {
-                                        Ok(param_pet) => param_pet,
-                                        Err(e) => return Ok(Response::builder()
-                                                        .status(StatusCode::BAD_REQUEST)
-                                                        .body(Body::from(format!("Couldn't parse body parameter Pet - doesn't match schema: {}", e)))
-                                                        .expect("Unable to create Bad Request response for invalid body parameter Pet due to schema")),
+                                        Ok(param_pet) => Some(param_pet),
+                                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't parse body parameter Pet - doesn't match schema: {}", e))),
                                     }
                                 } else {
                                     None
                                 };
+                                match unknown_fields.policy("UpdatePet") {
+                                    UnknownFields::Reject if !unused_elements.is_empty() => return Ok(problems.unknown_fields("Body parameter Pet has unknown fields", unused_elements)),
+                                    UnknownFields::Reject => {},
+                                    UnknownFields::Warn => for path in &unused_elements {
+                                        warn!("Ignoring unknown field in body: {}", path);
+                                    },
+                                    UnknownFields::Ignore => unused_elements.clear(),
+                                }
+                                if let Err(violations) = param_pet.validate() {
+                                    return Ok(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter Pet violates the schema", violations));
+                                }
                                 let param_pet = match param_pet {
                                     Some(param_pet) => param_pet,
-                                    None => return Ok(Response::builder()
-                                                        .status(StatusCode::BAD_REQUEST)
-                                                        .body(Body::from("Missing required body parameter Pet"))
-                                                        .expect("Unable to create Bad Request response for missing body parameter Pet")),
+                                    None => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::MissingBody, "Missing required body parameter Pet")),
                                 };
 
                                 let result = api_impl.update_pet(
@@ -772,9 +828,12 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for UPDATE_PET_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 UpdatePetResponse::InvalidIDSupplied
@@ -789,111 +848,70 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(405).expect("Unable to turn 405 into a StatusCode");
                                                 },
+                                                UpdatePetResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter Pet: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter Pet")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter Pet exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter Pet: {}", e))),
                         }
             },
 
             // UpdatePetWithForm - POST /pet/{petId}
-            hyper::Method::POST if path.matched(paths::ID_PET_PETID) => {
+            Some("UpdatePetWithForm") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
-
-                    // Authorization
-                    if let Scopes::Some(ref scopes) = authorization.scopes {
-                        let required_scopes: std::collections::BTreeSet<String> = vec![
-                            "write:pets".to_string(), // modify pets in your account
-                            "read:pets".to_string(), // read your pets
-                        ].into_iter().collect();
-
-                        if !required_scopes.is_subset(scopes) {
-                            let missing_scopes = required_scopes.difference(scopes);
-                            return Ok(Response::builder()
-                                .status(StatusCode::FORBIDDEN)
-                                .body(Body::from(missing_scopes.fold(
-                                    "Insufficient authorization, missing scopes".to_string(),
-                                    |s, scope| format!("{} {}", s, scope))
-                                ))
-                                .expect("Unable to create Authentication Insufficient response")
-                            );
-                        }
-                    }
                 }
 
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_PET_PETID
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE PET_PETID in set but failed match against \"{}\"", path, paths::REGEX_PET_PETID.as_str())
-                    );
-
-                let param_pet_id = match percent_encoding::percent_decode(path_params["petId"].as_bytes()).decode_utf8() {
+                let param_pet_id = match percent_encoding::percent_decode(path.param("petId").as_bytes()).decode_utf8() {
                     Ok(param_pet_id) => match param_pet_id.parse::<i64>() {
                         Ok(param_pet_id) => param_pet_id,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter petId: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter petId: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["petId"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("petId"))))
                 };
+                if let Err(violation) = validate::minimum("/petId", param_pet_id, 0) {
+                    return Ok(problems.violations(StatusCode::BAD_REQUEST, "Path parameter petId violates the schema", vec![violation]));
+                }
 
                 // Query parameters (note that non-required or collection query parameters will ignore garbage values, rather than causing a 400 response)
-                let query_params = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()).collect::<Vec<_>>();
-                let param_name = query_params.iter().filter(|e| e.0 == "name").map(|e| e.1.to_owned())
-                    .next();
+                let query_params = QueryParams::parse(uri.query().unwrap_or_default());
+                let param_name = query_params.get("name");
                 let param_name = match param_name {
                     Some(param_name) => {
                         let param_name =
                             <String as std::str::FromStr>::from_str
-                                (&param_name);
+                                (param_name);
                         match param_name {
                             Ok(param_name) => Some(param_name),
-                            Err(e) => return Ok(Response::builder()
-                                .status(StatusCode::BAD_REQUEST)
-                                .body(Body::from(format!("Couldn't parse query parameter name - doesn't match schema: {}", e)))
-                                .expect("Unable to create Bad Request response for invalid query parameter name")),
+                            Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter name - doesn't match schema: {}", e))),
                         }
                     },
                     None => None,
                 };
-                let param_status = query_params.iter().filter(|e| e.0 == "status").map(|e| e.1.to_owned())
-                    .next();
+                let param_status = query_params.get("status");
                 let param_status = match param_status {
                     Some(param_status) => {
                         let param_status =
                             <String as std::str::FromStr>::from_str
-                                (&param_status);
+                                (param_status);
                         match param_status {
                             Ok(param_status) => Some(param_status),
-                            Err(e) => return Ok(Response::builder()
-                                .status(StatusCode::BAD_REQUEST)
-                                .body(Body::from(format!("Couldn't parse query parameter status - doesn't match schema: {}", e)))
-                                .expect("Unable to create Bad Request response for invalid query parameter status")),
+                            Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter status - doesn't match schema: {}", e))),
                         }
                     },
                     None => None,
@@ -913,16 +931,23 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
 
                                         match result {
                                             Ok(rsp) => match rsp {
+                                                UpdatePetWithFormResponse::PetUpdated
+                                                => {
+                                                    *response.status_mut() = StatusCode::from_u16(204).expect("Unable to turn 204 into a StatusCode");
+                                                },
                                                 UpdatePetWithFormResponse::InvalidInput
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(405).expect("Unable to turn 405 into a StatusCode");
                                                 },
+                                                UpdatePetWithFormResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -930,29 +955,18 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // DeleteOrder - DELETE /store/order/{orderId}
-            hyper::Method::DELETE if path.matched(paths::ID_STORE_ORDER_ORDERID) => {
+            Some("DeleteOrder") => {
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_STORE_ORDER_ORDERID
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE STORE_ORDER_ORDERID in set but failed match against \"{}\"", path, paths::REGEX_STORE_ORDER_ORDERID.as_str())
-                    );
-
-                let param_order_id = match percent_encoding::percent_decode(path_params["orderId"].as_bytes()).decode_utf8() {
+                let param_order_id = match percent_encoding::percent_decode(path.param("orderId").as_bytes()).decode_utf8() {
                     Ok(param_order_id) => match param_order_id.parse::<i64>() {
                         Ok(param_order_id) => param_order_id,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter orderId: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter orderId: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["orderId"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("orderId"))))
                 };
+                if let Err(violation) = validate::minimum("/orderId", param_order_id, 0) {
+                    return Ok(problems.violations(StatusCode::BAD_REQUEST, "Path parameter orderId violates the schema", vec![violation]));
+                }
 
                                 let result = api_impl.delete_order(
                                             param_order_id,
@@ -974,12 +988,15 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(404).expect("Unable to turn 404 into a StatusCode");
                                                 },
+                                                DeleteOrderResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -987,17 +1004,21 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // GetInventory - GET /store/inventory
-            hyper::Method::GET if path.matched(paths::ID_STORE_INVENTORY) => {
+            Some("GetInventory") => {
                 {
                     let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
                         Some(ref authorization) => authorization,
-                        None => return Ok(Response::builder()
-                                                .status(StatusCode::FORBIDDEN)
-                                                .body(Body::from("Unauthenticated"))
-                                                .expect("Unable to create Authentication Forbidden response")),
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
                     };
                 }
 
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                                 let result = api_impl.get_inventory(
                                         &context
                                     ).await;
@@ -1015,17 +1036,20 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for GET_INVENTORY_SUCCESSFUL_OPERATION"));
                                                     let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
                                                     *response.body_mut() = Body::from(body);
                                                 },
+                                                GetInventoryResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1033,28 +1057,25 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // GetOrderById - GET /store/order/{orderId}
-            hyper::Method::GET if path.matched(paths::ID_STORE_ORDER_ORDERID) => {
+            Some("GetOrderById") => {
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_STORE_ORDER_ORDERID
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE STORE_ORDER_ORDERID in set but failed match against \"{}\"", path, paths::REGEX_STORE_ORDER_ORDERID.as_str())
-                    );
-
-                let param_order_id = match percent_encoding::percent_decode(path_params["orderId"].as_bytes()).decode_utf8() {
+                let param_order_id = match percent_encoding::percent_decode(path.param("orderId").as_bytes()).decode_utf8() {
                     Ok(param_order_id) => match param_order_id.parse::<i64>() {
                         Ok(param_order_id) => param_order_id,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter orderId: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter orderId: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["orderId"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("orderId"))))
+                };
+                if let Err(violation) = validate::minimum("/orderId", param_order_id, 0) {
+                    return Ok(problems.violations(StatusCode::BAD_REQUEST, "Path parameter orderId violates the schema", vec![violation]));
+                }
+
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
                 };
 
                                 let result = api_impl.get_order_by_id(
@@ -1075,9 +1096,12 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for GET_ORDER_BY_ID_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 GetOrderByIdResponse::InvalidIDSupplied
@@ -1088,12 +1112,15 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(404).expect("Unable to turn 404 into a StatusCode");
                                                 },
+                                                GetOrderByIdResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1101,26 +1128,47 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // PlaceOrder - POST /store/order
-            hyper::Method::POST if path.matched(paths::ID_STORE_ORDER) => {
+            Some("PlaceOrder") => {
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                 // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
+                // values, rather than causing a 400 response). Handle any unused fields as the
+                // operation's unknown fields policy says.
+                let content_type = match content::request_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                    MediaType::FormUrlEncoded,
+                ]) {
+                    Ok(content_type) => content_type,
+                    Err(e) => return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter Order - {}", e))),
+                };
+                let result = body::read(body, &headers, body_limits.limit("PlaceOrder")).await;
                 match result {
                             Ok(body) => {
                                 let mut unused_elements = Vec::new();
                                 let param_order: Option<models::Order> = if !body.is_empty() {
-                                    let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
-                                    }) {
-                                        Ok(param_order) => param_order,
-                                        Err(_) => None,
-                                    }
+                                    content::deserialize_body::<models::Order, _>(content_type, &body, |path| {
+                                            unused_elements.push(unknown_fields::pointer(&path));
+                                    }).ok()
                                 } else {
                                     None
                                 };
+                                match unknown_fields.policy("PlaceOrder") {
+                                    UnknownFields::Reject if !unused_elements.is_empty() => return Ok(problems.unknown_fields("Body parameter Order has unknown fields", unused_elements)),
+                                    UnknownFields::Reject => {},
+                                    UnknownFields::Warn => for path in &unused_elements {
+                                        warn!("Ignoring unknown field in body: {}", path);
+                                    },
+                                    UnknownFields::Ignore => unused_elements.clear(),
+                                }
+                                if let Err(violations) = param_order.validate() {
+                                    return Ok(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter Order violates the schema", violations));
+                                }
 
                                 let result = api_impl.place_order(
                                             param_order,
@@ -1147,7 +1195,7 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for PLACE_ORDER_SUCCESSFUL_OPERATION"));
                                                     let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
                                                     *response.body_mut() = Body::from(body);
@@ -1156,45 +1204,68 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(405).expect("Unable to turn 405 into a StatusCode");
                                                 },
+                                                PlaceOrderResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter Order: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter Order")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter Order exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter Order: {}", e))),
                         }
             },
 
             // CreateUser - POST /user
-            hyper::Method::POST if path.matched(paths::ID_USER) => {
+            Some("CreateUser") => {
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                 // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
+                // values, rather than causing a 400 response). Handle any unused fields as the
+                // operation's unknown fields policy says.
+                let content_type = match content::request_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                    MediaType::FormUrlEncoded,
+                ]) {
+                    Ok(content_type) => content_type,
+                    Err(e) => return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter User - {}", e))),
+                };
+                let result = body::read(body, &headers, body_limits.limit("CreateUser")).await;
                 match result {
                             Ok(body) => {
                                 let mut unused_elements = Vec::new();
                                 let param_user: Option<models::User> = if !body.is_empty() {
-                                    let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
-                                    }) {
-                                        Ok(param_user) => param_user,
-                                        Err(_) => None,
-                                    }
+                                    content::deserialize_body::<models::User, _>(content_type, &body, |path| {
+                                            unused_elements.push(unknown_fields::pointer(&path));
+                                    }).ok()
                                 } else {
                                     None
                                 };
+                                match unknown_fields.policy("CreateUser") {
+                                    UnknownFields::Reject if !unused_elements.is_empty() => return Ok(problems.unknown_fields("Body parameter User has unknown fields", unused_elements)),
+                                    UnknownFields::Reject => {},
+                                    UnknownFields::Warn => for path in &unused_elements {
+                                        warn!("Ignoring unknown field in body: {}", path);
+                                    },
+                                    UnknownFields::Ignore => unused_elements.clear(),
+                                }
+                                if let Err(violations) = param_user.validate() {
+                                    return Ok(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter User violates the schema", violations));
+                                }
 
                                 let result = api_impl.create_user(
                                             param_user,
@@ -1216,60 +1287,80 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                         match result {
                                             Ok(rsp) => match rsp {
                                                 CreateUserResponse::SuccessfulOperation
-                                                    (body)
+                                                    {
+                                                        status,
+                                                        body
+                                                    }
                                                 => {
-                                                    *response.status_mut() = StatusCode::from_u16(0).expect("Unable to turn 0 into a StatusCode");
-                                                    response.headers_mut().insert(
-                                                        CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
-                                                            .expect("Unable to create Content-Type header for CREATE_USER_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
-                                                    *response.body_mut() = Body::from(body);
+                                                    *response.status_mut() = default_status_code(status);
+                                                    if let Some(body) = body {
+                                                        response.headers_mut().insert(
+                                                            CONTENT_TYPE,
+                                                            HeaderValue::from_str(response_media_type.as_str())
+                                                                .expect("Unable to create Content-Type header for CREATE_USER_SUCCESSFUL_OPERATION"));
+                                                        let body = match response_media_type {
+                                                            MediaType::Xml => body.as_xml(),
+                                                            _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                        };
+                                                        *response.body_mut() = Body::from(body);
+                                                    }
+                                                },
+                                                CreateUserResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
                                                 },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter User: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter User")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter User exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter User: {}", e))),
                         }
             },
 
             // CreateUsersWithListInput - POST /user/createWithList
-            hyper::Method::POST if path.matched(paths::ID_USER_CREATEWITHLIST) => {
-                // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
-                match result {
-                            Ok(body) => {
-                                let mut unused_elements = Vec::new();
-                                let param_user: Option<Vec<models::User>> = if !body.is_empty() {
-                                    let deserializer = &mut serde_json::Deserializer::from_slice(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
-                                    }) {
-                                        Ok(param_user) => param_user,
-                                        Err(_) => None,
-                                    }
-                                } else {
-                                    None
-                                };
+            Some("CreateUsersWithListInput") => {
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
 
-                                let result = api_impl.create_users_with_list_input(
-                                            param_user.as_ref(),
+                // Body parameters, streamed to the implementation one element at a time. Handle
+                // any unused fields as the operation's unknown fields policy says.
+                if let Err(e) = content::request_media_type(&headers, &[
+                    MediaType::Json,
+                ]) {
+                    return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter User - {}", e)));
+                }
+                let outcome = Arc::new(Mutex::new(body::StreamOutcome::default()));
+                let result = body::json_array::<models::User>(body, &headers, body_limits.limit("CreateUsersWithListInput"), unknown_fields.policy("CreateUsersWithListInput"), outcome.clone()).await;
+                match result {
+                            Ok(param_user) => {
+                                let result = api_impl.create_users_with_list_input_stream(
+                                            param_user,
                                         &context
                                     ).await;
+
+                                // If the implementation failed on a body that turned out to be invalid, the
+                                // body's error is reported. A response that it chose regardless stands.
+                                let body::StreamOutcome { error, unused_elements } = std::mem::take(&mut *outcome.lock().expect("Stream outcome lock poisoned"));
+                                if let (Err(_), Some(error)) = (&result, error) {
+                                    return Ok(match error {
+                                        body::BodyError::TooLarge(limit) => problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter User exceeds the limit of {} bytes", limit)),
+                                        body::BodyError::Violations(violations) => problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter User violates the schema", violations),
+                                        body::BodyError::UnknownFields(pointers) => problems.unknown_fields("Body parameter User has unknown fields", pointers),
+                                        e => problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter User: {}", e)),
+                                    });
+                                }
                                 let mut response = Response::new(Body::empty());
                                 response.headers_mut().insert(
                                             HeaderName::from_static("x-span-id"),
@@ -1291,56 +1382,49 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for CREATE_USERS_WITH_LIST_INPUT_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 CreateUsersWithListInputResponse::SuccessfulOperation_2
+                                                    {
+                                                        status
+                                                    }
+                                                => {
+                                                    *response.status_mut() = default_status_code(status);
+                                                },
+                                                CreateUsersWithListInputResponse::Problem
+                                                    (problem)
                                                 => {
-                                                    *response.status_mut() = StatusCode::from_u16(0).expect("Unable to turn 0 into a StatusCode");
+                                                    problems.apply_problem(&mut response, problem);
                                                 },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter User: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter User")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter User exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter User: {}", e))),
                         }
             },
 
             // DeleteUser - DELETE /user/{username}
-            hyper::Method::DELETE if path.matched(paths::ID_USER_USERNAME) => {
+            Some("DeleteUser") => {
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_USER_USERNAME
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE USER_USERNAME in set but failed match against \"{}\"", path, paths::REGEX_USER_USERNAME.as_str())
-                    );
-
-                let param_username = match percent_encoding::percent_decode(path_params["username"].as_bytes()).decode_utf8() {
+                let param_username = match percent_encoding::percent_decode(path.param("username").as_bytes()).decode_utf8() {
                     Ok(param_username) => match param_username.parse::<String>() {
                         Ok(param_username) => param_username,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter username: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter username: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["username"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("username"))))
                 };
 
                                 let result = api_impl.delete_user(
@@ -1363,12 +1447,15 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(404).expect("Unable to turn 404 into a StatusCode");
                                                 },
+                                                DeleteUserResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1376,28 +1463,22 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // GetUserByName - GET /user/{username}
-            hyper::Method::GET if path.matched(paths::ID_USER_USERNAME) => {
+            Some("GetUserByName") => {
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_USER_USERNAME
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE USER_USERNAME in set but failed match against \"{}\"", path, paths::REGEX_USER_USERNAME.as_str())
-                    );
-
-                let param_username = match percent_encoding::percent_decode(path_params["username"].as_bytes()).decode_utf8() {
+                let param_username = match percent_encoding::percent_decode(path.param("username").as_bytes()).decode_utf8() {
                     Ok(param_username) => match param_username.parse::<String>() {
                         Ok(param_username) => param_username,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter username: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter username: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["username"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("username"))))
+                };
+
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
                 };
 
                                 let result = api_impl.get_user_by_name(
@@ -1418,9 +1499,12 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/json")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for GET_USER_BY_NAME_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Xml => body.as_xml(),
+                                                        _ => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 GetUserByNameResponse::InvalidUsernameSupplied
@@ -1431,12 +1515,15 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(404).expect("Unable to turn 404 into a StatusCode");
                                                 },
+                                                GetUserByNameResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1444,44 +1531,44 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // LoginUser - GET /user/login
-            hyper::Method::GET if path.matched(paths::ID_USER_LOGIN) => {
+            Some("LoginUser") => {
                 // Query parameters (note that non-required or collection query parameters will ignore garbage values, rather than causing a 400 response)
-                let query_params = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()).collect::<Vec<_>>();
-                let param_username = query_params.iter().filter(|e| e.0 == "username").map(|e| e.1.to_owned())
-                    .next();
+                let query_params = QueryParams::parse(uri.query().unwrap_or_default());
+                let param_username = query_params.get("username");
                 let param_username = match param_username {
                     Some(param_username) => {
                         let param_username =
                             <String as std::str::FromStr>::from_str
-                                (&param_username);
+                                (param_username);
                         match param_username {
                             Ok(param_username) => Some(param_username),
-                            Err(e) => return Ok(Response::builder()
-                                .status(StatusCode::BAD_REQUEST)
-                                .body(Body::from(format!("Couldn't parse query parameter username - doesn't match schema: {}", e)))
-                                .expect("Unable to create Bad Request response for invalid query parameter username")),
+                            Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter username - doesn't match schema: {}", e))),
                         }
                     },
                     None => None,
                 };
-                let param_password = query_params.iter().filter(|e| e.0 == "password").map(|e| e.1.to_owned())
-                    .next();
+                let param_password = query_params.get("password");
                 let param_password = match param_password {
                     Some(param_password) => {
                         let param_password =
                             <String as std::str::FromStr>::from_str
-                                (&param_password);
+                                (param_password);
                         match param_password {
                             Ok(param_password) => Some(param_password),
-                            Err(e) => return Ok(Response::builder()
-                                .status(StatusCode::BAD_REQUEST)
-                                .body(Body::from(format!("Couldn't parse query parameter password - doesn't match schema: {}", e)))
-                                .expect("Unable to create Bad Request response for invalid query parameter password")),
+                            Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse query parameter password - doesn't match schema: {}", e))),
                         }
                     },
                     None => None,
                 };
 
+                let response_media_type = match content::response_media_type(&headers, &[
+                    MediaType::Xml,
+                    MediaType::Json,
+                ]) {
+                    Ok(response_media_type) => response_media_type,
+                    Err(e) => return Ok(problems.response(StatusCode::NOT_ACCEPTABLE, ProblemType::NotAcceptable, e)),
+                };
+
                                 let result = api_impl.login_user(
                                             param_username,
                                             param_password,
@@ -1506,10 +1593,7 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     let x_rate_limit = match header::IntoHeaderValue(x_rate_limit).try_into() {
                                                         Ok(val) => val,
                                                         Err(e) => {
-                                                            return Ok(Response::builder()
-                                                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                                                    .body(Body::from(format!("An internal server error occurred handling x_rate_limit header - {}", e)))
-                                                                    .expect("Unable to create Internal Server Error for invalid response header"))
+                                                            return Ok(problems.response(StatusCode::INTERNAL_SERVER_ERROR, ProblemType::InternalError, format!("An internal server error occurred handling x_rate_limit header - {}", e)))
                                                         }
                                                     };
 
@@ -1522,10 +1606,7 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     let x_expires_after = match header::IntoHeaderValue(x_expires_after).try_into() {
                                                         Ok(val) => val,
                                                         Err(e) => {
-                                                            return Ok(Response::builder()
-                                                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                                                    .body(Body::from(format!("An internal server error occurred handling x_expires_after header - {}", e)))
-                                                                    .expect("Unable to create Internal Server Error for invalid response header"))
+                                                            return Ok(problems.response(StatusCode::INTERNAL_SERVER_ERROR, ProblemType::InternalError, format!("An internal server error occurred handling x_expires_after header - {}", e)))
                                                         }
                                                     };
 
@@ -1537,21 +1618,27 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                                     *response.status_mut() = StatusCode::from_u16(200).expect("Unable to turn 200 into a StatusCode");
                                                     response.headers_mut().insert(
                                                         CONTENT_TYPE,
-                                                        HeaderValue::from_str("application/xml")
+                                                        HeaderValue::from_str(response_media_type.as_str())
                                                             .expect("Unable to create Content-Type header for LOGIN_USER_SUCCESSFUL_OPERATION"));
-                                                    let body = serde_xml_rs::to_string(&body).expect("impossible to fail to serialize");
+                                                    let body = match response_media_type {
+                                                        MediaType::Json => serde_json::to_string(&body).expect("impossible to fail to serialize"),
+                                                        _ => serde_xml_rs::to_string(&body).expect("impossible to fail to serialize"),
+                                                    };
                                                     *response.body_mut() = Body::from(body);
                                                 },
                                                 LoginUserResponse::InvalidUsername
                                                 => {
                                                     *response.status_mut() = StatusCode::from_u16(400).expect("Unable to turn 400 into a StatusCode");
                                                 },
+                                                LoginUserResponse::Problem
+                                                    (problem)
+                                                => {
+                                                    problems.apply_problem(&mut response, problem);
+                                                },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1559,7 +1646,14 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // LogoutUser - GET /user/logout
-            hyper::Method::GET if path.matched(paths::ID_USER_LOGOUT) => {
+            Some("LogoutUser") => {
+                {
+                    let authorization = match *(&context as &dyn Has<Option<Authorization>>).get() {
+                        Some(ref authorization) => authorization,
+                        None => return Ok(problems.response(StatusCode::FORBIDDEN, ProblemType::Unauthenticated, "Unauthenticated")),
+                    };
+                }
+
                                 let result = api_impl.logout_user(
                                         &context
                                     ).await;
@@ -1572,15 +1666,21 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                         match result {
                                             Ok(rsp) => match rsp {
                                                 LogoutUserResponse::SuccessfulOperation
+                                                    {
+                                                        status
+                                                    }
+                                                => {
+                                                    *response.status_mut() = default_status_code(status);
+                                                },
+                                                LogoutUserResponse::Problem
+                                                    (problem)
                                                 => {
-                                                    *response.status_mut() = StatusCode::from_u16(0).expect("Unable to turn 0 into a StatusCode");
+                                                    problems.apply_problem(&mut response, problem);
                                                 },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
@@ -1588,49 +1688,49 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
             },
 
             // UpdateUser - PUT /user/{username}
-            hyper::Method::PUT if path.matched(paths::ID_USER_USERNAME) => {
+            Some("UpdateUser") => {
                 // Path parameters
-                let path: &str = uri.path();
-                let path_params =
-                    paths::REGEX_USER_USERNAME
-                    .captures(path)
-                    .unwrap_or_else(||
-                        panic!("Path {} matched RE USER_USERNAME in set but failed match against \"{}\"", path, paths::REGEX_USER_USERNAME.as_str())
-                    );
-
-                let param_username = match percent_encoding::percent_decode(path_params["username"].as_bytes()).decode_utf8() {
+                let param_username = match percent_encoding::percent_decode(path.param("username").as_bytes()).decode_utf8() {
                     Ok(param_username) => match param_username.parse::<String>() {
                         Ok(param_username) => param_username,
-                        Err(e) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't parse path parameter username: {}", e)))
-                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                        Err(e) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't parse path parameter username: {}", e))),
                     },
-                    Err(_) => return Ok(Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["username"])))
-                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                    Err(_) => return Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidParameter, format!("Couldn't percent-decode path parameter as UTF-8: {}", path.param("username"))))
                 };
 
                 // Body parameters (note that non-required body parameters will ignore garbage
-                // values, rather than causing a 400 response). Produce warning header and logs for
-                // any unused fields.
-                let result = body.into_raw().await;
+                // values, rather than causing a 400 response). Handle any unused fields as the
+                // operation's unknown fields policy says.
+                let content_type = match content::request_media_type(&headers, &[
+                    MediaType::Json,
+                    MediaType::Xml,
+                    MediaType::FormUrlEncoded,
+                ]) {
+                    Ok(content_type) => content_type,
+                    Err(e) => return Ok(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType, format!("Couldn't accept body parameter User - {}", e))),
+                };
+                let result = body::read(body, &headers, body_limits.limit("UpdateUser")).await;
                 match result {
                             Ok(body) => {
                                 let mut unused_elements = Vec::new();
                                 let param_user: Option<models::User> = if !body.is_empty() {
-                                    let deserializer = &mut serde_xml_rs::de::Deserializer::new_from_reader(&*body);
-                                    match serde_ignored::deserialize(deserializer, |path| {
-                                            warn!("Ignoring unknown field in body: {}", path);
-                                            unused_elements.push(path.to_string());
-                                    }) {
-                                        Ok(param_user) => param_user,
-                                        Err(_) => None,
-                                    }
+                                    content::deserialize_body::<models::User, _>(content_type, &body, |path| {
+                                            unused_elements.push(unknown_fields::pointer(&path));
+                                    }).ok()
                                 } else {
                                     None
                                 };
+                                match unknown_fields.policy("UpdateUser") {
+                                    UnknownFields::Reject if !unused_elements.is_empty() => return Ok(problems.unknown_fields("Body parameter User has unknown fields", unused_elements)),
+                                    UnknownFields::Reject => {},
+                                    UnknownFields::Warn => for path in &unused_elements {
+                                        warn!("Ignoring unknown field in body: {}", path);
+                                    },
+                                    UnknownFields::Ignore => unused_elements.clear(),
+                                }
+                                if let Err(violations) = param_user.validate() {
+                                    return Ok(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body parameter User violates the schema", violations));
+                                }
 
                                 let result = api_impl.update_user(
                                             param_username,
@@ -1653,89 +1753,71 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                                         match result {
                                             Ok(rsp) => match rsp {
                                                 UpdateUserResponse::SuccessfulOperation
+                                                    {
+                                                        status
+                                                    }
+                                                => {
+                                                    *response.status_mut() = default_status_code(status);
+                                                },
+                                                UpdateUserResponse::Problem
+                                                    (problem)
                                                 => {
-                                                    *response.status_mut() = StatusCode::from_u16(0).expect("Unable to turn 0 into a StatusCode");
+                                                    problems.apply_problem(&mut response, problem);
                                                 },
                                             },
-                                            Err(_) => {
-                                                // Application code returned an error. This should not happen, as the implementation should
-                                                // return a valid response.
-                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            Err(e) => {
+                                                // Application code returned an error - the error mapper decides how it is reported.
+                                                problems.apply_error(&mut response, &*error_mapper, &e);
                                             },
                                         }
 
                                         Ok(response)
                             },
-                            Err(e) => Ok(Response::builder()
-                                                .status(StatusCode::BAD_REQUEST)
-                                                .body(Body::from(format!("Couldn't read body parameter User: {}", e)))
-                                                .expect("Unable to create Bad Request response due to unable to read body parameter User")),
+                            Err(body::BodyError::TooLarge(limit)) => Ok(problems.response(StatusCode::PAYLOAD_TOO_LARGE, ProblemType::PayloadTooLarge, format!("Body parameter User exceeds the limit of {} bytes", limit))),
+                            Err(e) => Ok(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody, format!("Couldn't read body parameter User: {}", e))),
                         }
             },
 
-            _ if path.matched(paths::ID_PET) => method_not_allowed(),
-            _ if path.matched(paths::ID_PET_FINDBYSTATUS) => method_not_allowed(),
-            _ if path.matched(paths::ID_PET_FINDBYTAGS) => method_not_allowed(),
-            _ if path.matched(paths::ID_PET_PETID) => method_not_allowed(),
-            _ if path.matched(paths::ID_STORE_INVENTORY) => method_not_allowed(),
-            _ if path.matched(paths::ID_STORE_ORDER) => method_not_allowed(),
-            _ if path.matched(paths::ID_STORE_ORDER_ORDERID) => method_not_allowed(),
-            _ if path.matched(paths::ID_USER) => method_not_allowed(),
-            _ if path.matched(paths::ID_USER_CREATEWITHLIST) => method_not_allowed(),
-            _ if path.matched(paths::ID_USER_LOGIN) => method_not_allowed(),
-            _ if path.matched(paths::ID_USER_LOGOUT) => method_not_allowed(),
-            _ if path.matched(paths::ID_USER_USERNAME) => method_not_allowed(),
-            _ => Ok(Response::builder().status(StatusCode::NOT_FOUND)
-                    .body(Body::empty())
-                    .expect("Unable to create Not Found response"))
+            _ => match path.template() {
+                Some(template) if method == hyper::Method::OPTIONS => options(template),
+                Some(template) => method_not_allowed(&problems, &method, uri.path(), template),
+                None => Ok(problems.response(StatusCode::NOT_FOUND, ProblemType::NotFound, format!("No operation matches path {}", uri.path()))),
+            }
         }
-    } Box::pin(run(self.api_impl.clone(), req)) }
+    }
+
+        let (mut request, context) = req;
+
+        // HEAD is served by the path's GET operation, with the body stripped
+        let head = request.method() == hyper::Method::HEAD &&
+            self.router.matches(request.uri().path()).template().map_or(false, |template|
+                router::allowed_methods(template).contains(&hyper::Method::GET));
+        if head {
+            *request.method_mut() = hyper::Method::GET;
+        }
+
+        let response = run(self.api_impl.clone(), self.error_mapper.clone(), self.router.clone(), self.body_limits.clone(), self.unknown_fields.clone(), (request, context));
+        Box::pin(async move {
+            let mut response = response.await?;
+            if head {
+                let body = std::mem::replace(response.body_mut(), Body::empty());
+                if let Some(length) = hyper::body::HttpBody::size_hint(&body).exact() {
+                    response.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(length));
+                }
+            }
+            Ok(response)
+        })
+    }
 }
 
 /// Request parser for `Api`.
+///
+/// This matches paths below `BASE_PATH`, as it can't know a base path chosen with
+/// `with_base_path` - use `operation_id` on the `MakeService` or `Service` for those.
 pub struct ApiRequestParser;
 impl<T> RequestParser<T> for ApiRequestParser {
     fn parse_operation_id(request: &Request<T>) -> Option<&'static str> {
-        let path = paths::GLOBAL_REGEX_SET.matches(request.uri().path());
-        match *request.method() {
-            // AddPet - POST /pet
-            hyper::Method::POST if path.matched(paths::ID_PET) => Some("AddPet"),
-            // DeletePet - DELETE /pet/{petId}
-            hyper::Method::DELETE if path.matched(paths::ID_PET_PETID) => Some("DeletePet"),
-            // FindPetsByStatus - GET /pet/findByStatus
-            hyper::Method::GET if path.matched(paths::ID_PET_FINDBYSTATUS) => Some("FindPetsByStatus"),
-            // FindPetsByTags - GET /pet/findByTags
-            hyper::Method::GET if path.matched(paths::ID_PET_FINDBYTAGS) => Some("FindPetsByTags"),
-            // GetPetById - GET /pet/{petId}
-            hyper::Method::GET if path.matched(paths::ID_PET_PETID) => Some("GetPetById"),
-            // UpdatePet - PUT /pet
-            hyper::Method::PUT if path.matched(paths::ID_PET) => Some("UpdatePet"),
-            // UpdatePetWithForm - POST /pet/{petId}
-            hyper::Method::POST if path.matched(paths::ID_PET_PETID) => Some("UpdatePetWithForm"),
-            // DeleteOrder - DELETE /store/order/{orderId}
-            hyper::Method::DELETE if path.matched(paths::ID_STORE_ORDER_ORDERID) => Some("DeleteOrder"),
-            // GetInventory - GET /store/inventory
-            hyper::Method::GET if path.matched(paths::ID_STORE_INVENTORY) => Some("GetInventory"),
-            // GetOrderById - GET /store/order/{orderId}
-            hyper::Method::GET if path.matched(paths::ID_STORE_ORDER_ORDERID) => Some("GetOrderById"),
-            // PlaceOrder - POST /store/order
-            hyper::Method::POST if path.matched(paths::ID_STORE_ORDER) => Some("PlaceOrder"),
-            // CreateUser - POST /user
-            hyper::Method::POST if path.matched(paths::ID_USER) => Some("CreateUser"),
-            // CreateUsersWithListInput - POST /user/createWithList
-            hyper::Method::POST if path.matched(paths::ID_USER_CREATEWITHLIST) => Some("CreateUsersWithListInput"),
-            // DeleteUser - DELETE /user/{username}
-            hyper::Method::DELETE if path.matched(paths::ID_USER_USERNAME) => Some("DeleteUser"),
-            // GetUserByName - GET /user/{username}
-            hyper::Method::GET if path.matched(paths::ID_USER_USERNAME) => Some("GetUserByName"),
-            // LoginUser - GET /user/login
-            hyper::Method::GET if path.matched(paths::ID_USER_LOGIN) => Some("LoginUser"),
-            // LogoutUser - GET /user/logout
-            hyper::Method::GET if path.matched(paths::ID_USER_LOGOUT) => Some("LogoutUser"),
-            // UpdateUser - PUT /user/{username}
-            hyper::Method::PUT if path.matched(paths::ID_USER_USERNAME) => Some("UpdateUser"),
-            _ => None,
-        }
+        router::route(request.method(), &paths::ROUTER.matches(request.uri().path()))
+            .map(|route| route.operation_id)
     }
 }
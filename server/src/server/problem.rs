@@ -0,0 +1,91 @@
+//! Error responses generated by the router, as `application/problem+json` bodies.
+
+use hyper::{Body, Response, StatusCode};
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use log::error;
+use swagger::ApiError;
+
+use crate::problem::{Problem, ProblemType, PROBLEM_JSON};
+use crate::validate::Violation;
+use super::errors::{ApiErrorMapper, ErrorResponse};
+
+/// Builds the problem details responses for a single request, filling in the
+/// operation it was routed to and its X-Span-ID.
+#[derive(Debug, Clone)]
+pub(crate) struct Problems {
+    operation_id: Option<&'static str>,
+    span_id: String,
+}
+
+impl Problems {
+    pub(crate) fn new(operation_id: Option<&'static str>, span_id: String) -> Self {
+        Problems { operation_id, span_id }
+    }
+
+    /// An error response for a problem of the given kind.
+    pub(crate) fn response(&self, status: StatusCode, problem_type: ProblemType, detail: impl Into<String>) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        self.apply(&mut response, status, problem_type, detail);
+        response
+    }
+
+    /// An error response listing every schema constraint that the request violates.
+    pub(crate) fn violations(&self, status: StatusCode, detail: impl Into<String>, violations: Vec<Violation>) -> Response<Body> {
+        self.listing(status, ProblemType::ConstraintViolation, detail, violations)
+    }
+
+    /// A 400 response listing the JSON pointers of request body fields that the schema
+    /// doesn't define.
+    pub(crate) fn unknown_fields(&self, detail: impl Into<String>, pointers: Vec<String>) -> Response<Body> {
+        let violations = pointers.into_iter()
+            .map(|pointer| Violation::new(pointer, "is not a known field"))
+            .collect();
+        self.listing(StatusCode::BAD_REQUEST, ProblemType::UnknownFields, detail, violations)
+    }
+
+    fn listing(&self, status: StatusCode, problem_type: ProblemType, detail: impl Into<String>, violations: Vec<Violation>) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        let problem = Problem {
+            violations,
+            ..Problem::new(problem_type, status.as_u16(), detail)
+        };
+        self.write(&mut response, status, problem);
+        response
+    }
+
+    /// Turn an existing response into an error response for a problem of the given
+    /// kind, keeping any headers already set on it.
+    pub(crate) fn apply(&self, response: &mut Response<Body>, status: StatusCode, problem_type: ProblemType, detail: impl Into<String>) {
+        self.write(response, status, Problem::new(problem_type, status.as_u16(), detail));
+    }
+
+    /// Turn an existing response into the error response that `mapper` chooses for an
+    /// error returned by the `Api` implementation, logging the error.
+    pub(crate) fn apply_error(&self, response: &mut Response<Body>, mapper: &dyn ApiErrorMapper, error: &ApiError) {
+        let operation_id = self.operation_id.unwrap_or_default();
+        error!("{} failed - X-Span-ID: {} - {}", operation_id, self.span_id, error);
+
+        let ErrorResponse { status, headers, problem } = mapper.map_error(operation_id, error);
+        response.headers_mut().extend(headers);
+        self.write(response, status, problem);
+    }
+
+    /// Turn an existing response into an error response for a problem returned by the
+    /// `Api` implementation, sent with the problem's status code.
+    pub(crate) fn apply_problem(&self, response: &mut Response<Body>, problem: Problem) {
+        let status = StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        self.write(response, status, problem);
+    }
+
+    fn write(&self, response: &mut Response<Body>, status: StatusCode, problem: Problem) {
+        let problem = Problem {
+            status: status.as_u16(),
+            instance: Some(self.span_id.clone()),
+            operation_id: self.operation_id.map(str::to_string),
+            ..problem
+        };
+        *response.status_mut() = status;
+        response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        *response.body_mut() = Body::from(serde_json::to_string(&problem).expect("impossible to fail to serialize"));
+    }
+}
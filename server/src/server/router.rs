@@ -0,0 +1,230 @@
+//! Path routing for the server.
+//!
+//! Path templates (static segments plus `{param}` captures) are compiled into a trie,
+//! so that a request path is matched and its parameters extracted in a single pass.
+
+use hyper::Method;
+
+/// An operation in the route table.
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// HTTP method of the operation
+    pub method: Method,
+    /// Path template, relative to the base path, e.g. `/pet/{petId}`
+    pub path: &'static str,
+    /// The operation's `operationId`
+    pub operation_id: &'static str,
+    /// OAuth2 scopes that the operation requires, checked before it's dispatched
+    pub scopes: &'static [&'static str],
+}
+
+/// Every operation that the server routes.
+pub static ROUTES: &[Route] = &[
+    Route { method: Method::POST, path: "/pet", operation_id: "AddPet", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::DELETE, path: "/pet/{petId}", operation_id: "DeletePet", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::GET, path: "/pet/findByStatus", operation_id: "FindPetsByStatus", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::GET, path: "/pet/findByTags", operation_id: "FindPetsByTags", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::GET, path: "/pet/{petId}", operation_id: "GetPetById", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::PUT, path: "/pet", operation_id: "UpdatePet", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::POST, path: "/pet/{petId}", operation_id: "UpdatePetWithForm", scopes: &["write:pets", "read:pets"] },
+    Route { method: Method::DELETE, path: "/store/order/{orderId}", operation_id: "DeleteOrder", scopes: &[] },
+    Route { method: Method::GET, path: "/store/inventory", operation_id: "GetInventory", scopes: &[] },
+    Route { method: Method::GET, path: "/store/order/{orderId}", operation_id: "GetOrderById", scopes: &[] },
+    Route { method: Method::POST, path: "/store/order", operation_id: "PlaceOrder", scopes: &[] },
+    Route { method: Method::POST, path: "/user", operation_id: "CreateUser", scopes: &[] },
+    Route { method: Method::POST, path: "/user/createWithList", operation_id: "CreateUsersWithListInput", scopes: &[] },
+    Route { method: Method::DELETE, path: "/user/{username}", operation_id: "DeleteUser", scopes: &[] },
+    Route { method: Method::GET, path: "/user/{username}", operation_id: "GetUserByName", scopes: &[] },
+    Route { method: Method::GET, path: "/user/login", operation_id: "LoginUser", scopes: &[] },
+    Route { method: Method::GET, path: "/user/logout", operation_id: "LogoutUser", scopes: &[] },
+    Route { method: Method::PUT, path: "/user/{username}", operation_id: "UpdateUser", scopes: &[] },
+];
+
+/// The distinct path templates of `ROUTES`, in the order they first appear.
+pub fn templates() -> Vec<&'static str> {
+    let mut templates = Vec::new();
+    for route in ROUTES {
+        if !templates.contains(&route.path) {
+            templates.push(route.path);
+        }
+    }
+    templates
+}
+
+/// The operation in `ROUTES` with the given `operationId`, e.g. `AddPet`.
+pub fn operation(operation_id: &str) -> Option<&'static Route> {
+    ROUTES.iter().find(|route| route.operation_id == operation_id)
+}
+
+/// The operation in `ROUTES` that a request with `method` on the matched path is
+/// routed to.
+pub fn route(method: &Method, path: &PathMatch<'_>) -> Option<&'static Route> {
+    let template = path.template()?;
+    ROUTES.iter().find(|route| route.method == *method && route.path == template)
+}
+
+/// The methods allowed on a path template: those of the operations registered for it,
+/// plus HEAD wherever GET is allowed, and OPTIONS.
+pub fn allowed_methods(path: &str) -> Vec<Method> {
+    let mut methods = ROUTES.iter()
+        .filter(|route| route.path == path)
+        .map(|route| route.method.clone())
+        .collect::<Vec<_>>();
+    if methods.contains(&Method::GET) {
+        methods.push(Method::HEAD);
+    }
+    methods.push(Method::OPTIONS);
+    methods
+}
+
+/// Matches request paths against a set of path templates.
+///
+/// Static segments take precedence over `{param}` captures, so `/pet/findByStatus`
+/// matches that template rather than `/pet/{petId}`.
+#[derive(Debug, Clone)]
+pub struct PathRouter {
+    base_path: String,
+    root: Node,
+    templates: Vec<&'static str>,
+    /// Parameter names of each template, in path order
+    params: Vec<Vec<&'static str>>,
+}
+
+impl PathRouter {
+    /// Build a router for `templates`, which are matched below `base_path` (`""` or `/` for
+    /// the root).
+    pub fn new(base_path: &str, templates: &[&'static str]) -> Self {
+        let mut root = Node::default();
+        let mut params = Vec::with_capacity(templates.len());
+
+        for (id, template) in templates.iter().enumerate() {
+            let segments = template.trim_start_matches('/').split('/').collect::<Vec<_>>();
+            root.insert(&segments, id);
+            params.push(segments.iter().filter_map(|segment| param_name(segment)).collect());
+        }
+
+        PathRouter {
+            base_path: crate::normalize_base_path(base_path),
+            root,
+            templates: templates.to_vec(),
+            params,
+        }
+    }
+
+    /// Build a router for the templates of `ROUTES`, matched below `base_path`.
+    pub fn for_routes(base_path: &str) -> Self {
+        Self::new(base_path, &templates())
+    }
+
+    /// Match a request path, extracting the raw (still percent-encoded) values of its
+    /// path parameters.
+    pub fn matches<'a>(&self, path: &'a str) -> PathMatch<'a> {
+        let path = match path.strip_prefix(self.base_path.as_str()).and_then(|path| path.strip_prefix('/')) {
+            Some(path) => path,
+            None => return PathMatch::default(),
+        };
+
+        let mut values = Vec::new();
+        match self.root.find(path, &mut values) {
+            Some(id) => PathMatch {
+                template: Some(self.templates[id]),
+                params: self.params[id].iter().copied().zip(values).collect(),
+            },
+            None => PathMatch::default(),
+        }
+    }
+}
+
+/// The result of matching a request path.
+#[derive(Debug, Clone, Default)]
+pub struct PathMatch<'a> {
+    template: Option<&'static str>,
+    params: Vec<(&'static str, &'a str)>,
+}
+
+impl<'a> PathMatch<'a> {
+    /// The template that the path matched, if any.
+    pub fn template(&self) -> Option<&'static str> {
+        self.template
+    }
+
+    /// The raw value of a path parameter. Parameters that the matched template doesn't
+    /// capture are empty.
+    pub fn param(&self, name: &str) -> &'a str {
+        self.params.iter()
+            .find(|(param, _)| *param == name)
+            .map_or("", |(_, value)| value)
+    }
+}
+
+fn param_name(segment: &'static str) -> Option<&'static str> {
+    segment.strip_prefix('{').and_then(|segment| segment.strip_suffix('}'))
+}
+
+/// A node of the trie - one per path segment.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Children for static segments
+    statics: Vec<(&'static str, Node)>,
+    /// Child for a `{param}` segment
+    param: Option<Box<Node>>,
+    /// Template that ends at this node
+    template: Option<usize>,
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[&'static str], id: usize) {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => {
+                // The first of two identical templates wins
+                self.template.get_or_insert(id);
+                return;
+            },
+        };
+
+        let child = if param_name(segment).is_some() {
+            self.param.get_or_insert_with(Box::default)
+        } else {
+            let index = match self.statics.iter().position(|(name, _)| name == segment) {
+                Some(index) => index,
+                None => {
+                    self.statics.push((segment, Node::default()));
+                    self.statics.len() - 1
+                },
+            };
+            &mut self.statics[index].1
+        };
+        child.insert(rest, id);
+    }
+
+    /// Find the template matching `path`, which has had its leading `/` removed,
+    /// backtracking from static segments to parameters where necessary.
+    fn find<'a>(&self, path: &'a str, values: &mut Vec<&'a str>) -> Option<usize> {
+        let (segment, rest) = match path.split_once('/') {
+            Some((segment, rest)) => (segment, Some(rest)),
+            None => (path, None),
+        };
+
+        let next = |node: &Node, values: &mut Vec<&'a str>| match rest {
+            Some(rest) => node.find(rest, values),
+            None => node.template,
+        };
+
+        if let Some((_, child)) = self.statics.iter().find(|(name, _)| *name == segment) {
+            if let Some(id) = next(child, values) {
+                return Some(id);
+            }
+        }
+
+        if let Some(ref child) = self.param {
+            values.push(segment);
+            if let Some(id) = next(child, values) {
+                return Some(id);
+            }
+            values.pop();
+        }
+
+        None
+    }
+}
@@ -0,0 +1,315 @@
+//! Sessions started by `loginUser` and ended by `logoutUser`.
+//!
+//! Logging in issues an opaque token, which later requests send as their `api_key` or
+//! as a bearer token. Add `Sessions` to the `MakeAuthenticator` both ways so that either
+//! is accepted:
+//!
+//! ```ignore
+//! let sessions = Sessions::new(InMemorySessionStore::new());
+//! let service = MakeAuthenticator::new(MakeService::new(server), keys)
+//!     .with_keys(sessions.clone())
+//!     .with_bearer(sessions);
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use log::error;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use swagger::auth::{AuthData, Authorization, Scopes};
+
+use super::auth::{KeyStore, TokenError, TokenValidator};
+
+/// How long a session lasts unless configured otherwise: one hour.
+pub const DEFAULT_SESSION_LIFETIME_SECS: i64 = 60 * 60;
+
+/// A logged in user's session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// The opaque token identifying the session
+    pub token: String,
+    /// Who logged in
+    pub subject: String,
+    pub scopes: BTreeSet<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    fn authorization(&self) -> Authorization {
+        Authorization {
+            subject: self.subject.clone(),
+            scopes: Scopes::Some(self.scopes.clone()),
+            issuer: None,
+        }
+    }
+}
+
+/// A session store that couldn't be read or written.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The file isn't a JSON list of sessions
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "Couldn't access session file: {}", e),
+            SessionError::Json(e) => write!(f, "Couldn't parse session file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<io::Error> for SessionError {
+    fn from(e: io::Error) -> Self {
+        SessionError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SessionError {
+    fn from(e: serde_json::Error) -> Self {
+        SessionError::Json(e)
+    }
+}
+
+/// Where sessions are kept, by token. Expiry is left to `Sessions`.
+pub trait SessionStore: Send + Sync {
+    fn insert(&self, session: Session) -> Result<(), SessionError>;
+
+    fn get(&self, token: &str) -> Result<Option<Session>, SessionError>;
+
+    /// Remove a session, returning it if it existed.
+    fn remove(&self, token: &str) -> Result<Option<Session>, SessionError>;
+
+    /// Remove every session that has expired by `now`.
+    fn remove_expired(&self, now: DateTime<Utc>) -> Result<(), SessionError>;
+}
+
+impl<S: SessionStore + ?Sized> SessionStore for Arc<S> {
+    fn insert(&self, session: Session) -> Result<(), SessionError> {
+        (**self).insert(session)
+    }
+
+    fn get(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        (**self).get(token)
+    }
+
+    fn remove(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        (**self).remove(token)
+    }
+
+    fn remove_expired(&self, now: DateTime<Utc>) -> Result<(), SessionError> {
+        (**self).remove_expired(now)
+    }
+}
+
+/// Sessions held in memory, and lost when the server stops.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        InMemorySessionStore::default()
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn insert(&self, session: Session) -> Result<(), SessionError> {
+        let mut sessions = self.sessions.write().expect("Session store lock poisoned");
+        sessions.insert(session.token.clone(), session);
+        Ok(())
+    }
+
+    fn get(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        Ok(self.sessions.read().expect("Session store lock poisoned").get(token).cloned())
+    }
+
+    fn remove(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        Ok(self.sessions.write().expect("Session store lock poisoned").remove(token))
+    }
+
+    fn remove_expired(&self, now: DateTime<Utc>) -> Result<(), SessionError> {
+        self.sessions.write().expect("Session store lock poisoned")
+            .retain(|_, session| !session.is_expired(now));
+        Ok(())
+    }
+}
+
+/// Sessions kept in a JSON file, so that they survive a restart. The whole file is
+/// rewritten on every change, which suits a single server with modest traffic.
+#[derive(Debug)]
+pub struct FileSessionStore {
+    path: PathBuf,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl FileSessionStore {
+    /// Use the file at `path`, loading any sessions already in it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        let path = path.as_ref().to_path_buf();
+        let sessions = match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice::<Vec<Session>>(&contents)?
+                .into_iter()
+                .map(|session| (session.token.clone(), session))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(FileSessionStore {
+            path,
+            sessions: Mutex::new(sessions),
+        })
+    }
+
+    /// Write the sessions to a temporary file and move it into place, so that the file
+    /// is never left half written.
+    fn save(&self, sessions: &HashMap<String, Session>) -> Result<(), SessionError> {
+        let contents = serde_json::to_vec_pretty(&sessions.values().collect::<Vec<_>>())?;
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn insert(&self, session: Session) -> Result<(), SessionError> {
+        let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+        sessions.insert(session.token.clone(), session);
+        self.save(&sessions)
+    }
+
+    fn get(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        Ok(self.sessions.lock().expect("Session store lock poisoned").get(token).cloned())
+    }
+
+    fn remove(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+        let session = sessions.remove(token);
+        if session.is_some() {
+            self.save(&sessions)?;
+        }
+        Ok(session)
+    }
+
+    fn remove_expired(&self, now: DateTime<Utc>) -> Result<(), SessionError> {
+        let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+        let count = sessions.len();
+        sessions.retain(|_, session| !session.is_expired(now));
+        if sessions.len() != count {
+            self.save(&sessions)?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts, looks up and ends sessions in a `SessionStore`.
+#[derive(Clone)]
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    lifetime: Duration,
+}
+
+impl Sessions {
+    /// Keep sessions in `store`, each lasting `DEFAULT_SESSION_LIFETIME_SECS`.
+    pub fn new(store: impl SessionStore + 'static) -> Self {
+        Sessions {
+            store: Arc::new(store),
+            lifetime: Duration::seconds(DEFAULT_SESSION_LIFETIME_SECS),
+        }
+    }
+
+    /// Make sessions last for `lifetime`.
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Start a session for `subject`, granting `scopes`, with a new token. Sessions
+    /// that have expired are cleared out first.
+    pub fn login<S: Into<String>>(&self, subject: impl Into<String>, scopes: impl IntoIterator<Item = S>) -> Result<Session, SessionError> {
+        let now = Utc::now();
+        self.store.remove_expired(now)?;
+
+        let session = Session {
+            token: new_token(),
+            subject: subject.into(),
+            scopes: scopes.into_iter().map(Into::into).collect(),
+            expires_at: now + self.lifetime,
+        };
+        self.store.insert(session.clone())?;
+        Ok(session)
+    }
+
+    /// End the session whose token the request carried, as its `api_key` or bearer
+    /// token - for `logoutUser`, passing the context's `Option<AuthData>`. Returns the
+    /// session, if there was one.
+    pub fn logout(&self, auth_data: &Option<AuthData>) -> Result<Option<Session>, SessionError> {
+        let token = match auth_data {
+            Some(AuthData::ApiKey(api_key)) => api_key.as_str(),
+            Some(AuthData::Bearer(bearer)) => bearer.token.as_str(),
+            _ => return Ok(None),
+        };
+        self.store.remove(token)
+    }
+
+    /// The session with the given token, unless it has expired.
+    pub fn session(&self, token: &str) -> Result<Option<Session>, SessionError> {
+        let session = self.store.get(token)?;
+        Ok(session.filter(|session| !session.is_expired(Utc::now())))
+    }
+
+    fn authorize_token(&self, token: &str) -> Option<Authorization> {
+        match self.session(token) {
+            Ok(session) => session.as_ref().map(Session::authorization),
+            Err(e) => {
+                error!("Unable to look up session: {}", e);
+                None
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Sessions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sessions")
+            .field("lifetime", &self.lifetime)
+            .finish()
+    }
+}
+
+impl KeyStore for Sessions {
+    fn authorize(&self, api_key: &str) -> Option<Authorization> {
+        self.authorize_token(api_key)
+    }
+}
+
+impl TokenValidator for Sessions {
+    fn validate(&self, token: &str) -> Result<Authorization, TokenError> {
+        self.authorize_token(token)
+            .ok_or_else(|| TokenError("Session is unknown or has expired".to_string()))
+    }
+}
+
+/// 32 random bytes from the operating system, hex-encoded.
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
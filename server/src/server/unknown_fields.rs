@@ -0,0 +1,73 @@
+//! What the server does with request body fields that the schema doesn't define.
+
+use std::collections::HashMap;
+
+use super::router;
+
+/// How to handle request body fields that the schema doesn't define - often a client's
+/// typo, such as `photoUrl` for `photoUrls`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownFields {
+    /// Accept the body, dropping the fields silently
+    Ignore,
+    /// Accept the body, logging the fields and listing them in a `Warning` header
+    Warn,
+    /// Reject the body with a 400 listing the fields
+    Reject,
+}
+
+/// How each operation handles unknown request body fields.
+#[derive(Debug, Clone)]
+pub struct UnknownFieldsPolicy {
+    default: UnknownFields,
+    operations: HashMap<String, UnknownFields>,
+}
+
+impl Default for UnknownFieldsPolicy {
+    /// Warn about unknown fields in every operation. This is what the server did before
+    /// the policy could be chosen, so it stays the default: unknown fields were logged and
+    /// listed in a `Warning` header, rather than ignored.
+    fn default() -> Self {
+        UnknownFieldsPolicy::new(UnknownFields::Warn)
+    }
+}
+
+impl UnknownFieldsPolicy {
+    /// Handle unknown fields as `default` says in every operation.
+    pub fn new(default: UnknownFields) -> Self {
+        UnknownFieldsPolicy {
+            default,
+            operations: HashMap::new(),
+        }
+    }
+
+    /// Handle unknown fields in the operation with the given ID, e.g. `AddPet`, as
+    /// `unknown_fields` says.
+    ///
+    /// # Panics
+    ///
+    /// If there's no operation with the given ID in `router::ROUTES`.
+    pub fn operation(mut self, operation_id: impl Into<String>, unknown_fields: UnknownFields) -> Self {
+        let operation_id = operation_id.into();
+        assert!(router::operation(&operation_id).is_some(), "No operation has the ID {}", operation_id);
+        self.operations.insert(operation_id, unknown_fields);
+        self
+    }
+
+    /// How an operation handles unknown fields.
+    pub fn policy(&self, operation_id: &str) -> UnknownFields {
+        self.operations.get(operation_id).copied().unwrap_or(self.default)
+    }
+}
+
+/// The JSON pointer to a field that deserialization ignored, e.g. `/tags/0/nme`.
+pub(crate) fn pointer(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => format!("{}/{}", pointer(parent), index),
+        serde_ignored::Path::Map { parent, key } => format!("{}/{}", pointer(parent), key.replace('~', "~0").replace('/', "~1")),
+        serde_ignored::Path::Some { parent } |
+        serde_ignored::Path::NewtypeStruct { parent } |
+        serde_ignored::Path::NewtypeVariant { parent } => pointer(parent),
+    }
+}
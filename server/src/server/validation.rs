@@ -0,0 +1,495 @@
+//! Middleware that checks requests against the API definition before they reach a
+//! `Service`, so that the `Api` implementation only sees requests that conform to it.
+//!
+//! Requests for paths or methods that the definition doesn't describe are passed on
+//! unchanged, for the `Service` to reject.
+
+use futures::future::BoxFuture;
+use hyper::header::CONTENT_TYPE;
+use hyper::http::request::Parts;
+use hyper::service::Service;
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use swagger::{Has, XSpanIdString};
+use tower::Layer;
+
+use crate::form;
+use crate::media::MediaType;
+use crate::problem::ProblemType;
+use crate::query::QueryParams;
+use crate::spec::{Location, Operation, Parameter, RequestBody, Spec};
+use crate::validate::Violation;
+use super::body::{self, BodyError, BodyLimits};
+use super::problem::Problems;
+use super::router::ROUTES;
+
+#[derive(Debug, Clone)]
+pub struct MakeValidateRequests<T> {
+    inner: T,
+    spec: Arc<Spec>,
+    body_limits: Arc<BodyLimits>,
+}
+
+impl<T> MakeValidateRequests<T> {
+    /// Create a middleware that checks requests against `spec`, which is usually loaded
+    /// from `api/openapi.yaml` at startup.
+    pub fn new(inner: T, spec: Spec) -> Self {
+        MakeValidateRequests {
+            inner,
+            spec: Arc::new(spec),
+            body_limits: Arc::new(BodyLimits::default()),
+        }
+    }
+
+    /// Limit the size of the request bodies that are read to check them, rather than to
+    /// `body::DEFAULT_MAX_BODY_SIZE`. This should be the `BodyLimits` given to the
+    /// `MakeService`.
+    pub fn with_body_limits(mut self, body_limits: BodyLimits) -> Self {
+        self.body_limits = Arc::new(body_limits);
+        self
+    }
+}
+
+impl<T, Target> Service<Target> for MakeValidateRequests<T>
+where
+    T: Service<Target>,
+    T::Future: Send + 'static,
+{
+    type Error = T::Error;
+    type Response = ValidateRequests<T::Response>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        let spec = self.spec.clone();
+        let body_limits = self.body_limits.clone();
+        let service = self.inner.call(target);
+        Box::pin(async move {
+            Ok(ValidateRequests { inner: service.await?, spec, body_limits })
+        })
+    }
+}
+
+/// A `tower::Layer` that wraps services in `ValidateRequests`.
+#[derive(Debug, Clone)]
+pub struct ValidateRequestsLayer {
+    spec: Arc<Spec>,
+    body_limits: Arc<BodyLimits>,
+}
+
+impl ValidateRequestsLayer {
+    /// Check requests against `spec`.
+    pub fn new(spec: Spec) -> Self {
+        ValidateRequestsLayer {
+            spec: Arc::new(spec),
+            body_limits: Arc::new(BodyLimits::default()),
+        }
+    }
+
+    /// Limit the size of the request bodies that are read to check them, rather than to
+    /// `body::DEFAULT_MAX_BODY_SIZE`. This should be the `BodyLimits` given to the
+    /// `Service`.
+    pub fn with_body_limits(mut self, body_limits: BodyLimits) -> Self {
+        self.body_limits = Arc::new(body_limits);
+        self
+    }
+}
+
+impl<S> Layer<S> for ValidateRequestsLayer {
+    type Service = ValidateRequests<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidateRequests {
+            inner,
+            spec: self.spec.clone(),
+            body_limits: self.body_limits.clone(),
+        }
+    }
+}
+
+/// Rejects requests whose parameters or body don't conform to the API definition.
+///
+/// Missing required parameters and parameters that violate their schema are a 400, a
+/// body of an undeclared media type is a 415 and a body that violates its schema is a
+/// 422, each with a problem details body listing what's wrong. XML bodies are only
+/// checked for their media type.
+#[derive(Debug, Clone)]
+pub struct ValidateRequests<T> {
+    inner: T,
+    spec: Arc<Spec>,
+    body_limits: Arc<BodyLimits>,
+}
+
+impl<T, C> Service<(Request<Body>, C)> for ValidateRequests<T>
+where
+    T: Service<(Request<Body>, C), Response = Response<Body>> + Clone + Send + 'static,
+    T::Future: Send + 'static,
+    C: Has<XSpanIdString> + Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = T::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, (request, context): (Request<Body>, C)) -> Self::Future {
+        // Take the service that was polled ready, leaving a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let spec = self.spec.clone();
+        let body_limits = self.body_limits.clone();
+
+        Box::pin(async move {
+            let span_id = Has::<XSpanIdString>::get(&context).0.clone();
+            let (parts, body) = request.into_parts();
+            let (body, rejection) = check(&spec, &body_limits, &parts, body, span_id).await;
+            match rejection {
+                Some(response) => Ok(response),
+                None => inner.call((Request::from_parts(parts, body), context)).await,
+            }
+        })
+    }
+}
+
+/// Check a request, returning its body - buffered, if it had to be read - and the
+/// response rejecting it, if it doesn't conform.
+async fn check(spec: &Spec, body_limits: &BodyLimits, parts: &Parts, body: Body, span_id: String) -> (Body, Option<Response<Body>>) {
+    let (operation, path_params) = match spec.find(&operation_method(&parts.method), parts.uri.path()) {
+        Some(found) => found,
+        None => return (body, None),
+    };
+    let operation_id = operation_id(operation);
+    let problems = Problems::new(operation_id, span_id);
+
+    let query = QueryParams::parse(parts.uri.query().unwrap_or_default());
+    let mut violations = Vec::new();
+    for parameter in &operation.parameters {
+        let is_array = spec.resolve(&parameter.schema).get("type").and_then(Value::as_str) == Some("array");
+        let value = match parameter.location {
+            // Query arrays are split the same way as in the `Service`
+            Location::Query if is_array => match query.array(&parameter.name, parameter.style, parameter.explode) {
+                Ok(items) => items.map(|items| Value::Array(items.into_iter().map(Value::String).collect())),
+                Err(e) => {
+                    violations.push(Violation::new(format!("/query/{}", parameter.name), e));
+                    continue;
+                },
+            },
+            Location::Query => query.get(&parameter.name).map(|value| Value::String(value.to_string())),
+            Location::Path => path_params.iter()
+                .find(|(name, _)| *name == parameter.name)
+                .map(|(_, value)| simple(percent_decode_str(value).decode_utf8_lossy().into_owned(), is_array)),
+            Location::Header => parts.headers.get(parameter.name.as_str())
+                .map(|value| simple(String::from_utf8_lossy(value.as_bytes()).into_owned(), is_array)),
+        };
+        check_parameter(spec, parameter, value, &mut violations);
+    }
+    if !violations.is_empty() {
+        return (body, Some(problems.violations(StatusCode::BAD_REQUEST, "Parameters violate the schema", violations)));
+    }
+
+    let request_body = match operation.request_body {
+        Some(ref request_body) => request_body,
+        None => return (body, None),
+    };
+    let bytes = match body::read(body, &parts.headers, body_limits.limit(operation_id.unwrap_or_default())).await {
+        Ok(bytes) => bytes,
+        Err(BodyError::TooLarge(limit)) => return (Body::empty(), Some(problems.response(StatusCode::PAYLOAD_TOO_LARGE,
+            ProblemType::PayloadTooLarge, format!("Body exceeds the limit of {} bytes", limit)))),
+        Err(e) => return (Body::empty(), Some(problems.response(StatusCode::BAD_REQUEST,
+            ProblemType::InvalidBody, format!("Unable to read body: {}", e)))),
+    };
+    let rejection = check_body(spec, request_body, &parts.headers, &bytes, &problems);
+    (Body::from(bytes), rejection)
+}
+
+/// The `&'static` operation ID of an operation in the route table, for problem details.
+fn operation_id(operation: &Operation) -> Option<&'static str> {
+    ROUTES.iter()
+        .find(|route| route.method == operation.method && route.path == operation.path)
+        .map(|route| route.operation_id)
+}
+
+fn check_parameter(spec: &Spec, parameter: &Parameter, value: Option<Value>, violations: &mut Vec<Violation>) {
+    let pointer = format!("/{}/{}", parameter.location.as_str(), parameter.name);
+
+    let value = match value {
+        Some(value) => value,
+        None => {
+            if parameter.required {
+                violations.push(Violation::new(pointer, "is required"));
+            }
+            return;
+        },
+    };
+
+    let value = spec.coerce(&parameter.schema, value);
+    spec.validate(&parameter.schema, &value, &pointer, violations);
+}
+
+/// A path or header parameter's value, in the `simple` style: arrays are comma-separated.
+fn simple(value: String, is_array: bool) -> Value {
+    if is_array {
+        Value::Array(value.split(',').map(|item| Value::String(item.to_string())).collect())
+    } else {
+        Value::String(value)
+    }
+}
+
+/// The response rejecting a request body, if it doesn't conform.
+fn check_body(spec: &Spec, request_body: &RequestBody, headers: &HeaderMap, body: &[u8], problems: &Problems) -> Option<Response<Body>> {
+    if body.is_empty() {
+        if request_body.required {
+            return Some(problems.response(StatusCode::BAD_REQUEST, ProblemType::MissingBody, "Missing required body"));
+        }
+        return None;
+    }
+
+    let declared = request_body.content.iter()
+        .filter_map(|(media_type, schema)| media_type.parse::<MediaType>().ok().map(|media_type| (media_type, schema)))
+        .collect::<Vec<_>>();
+
+    // As in the `Service`, a missing Content-Type selects the first declared media type
+    let content_type = match headers.get(CONTENT_TYPE) {
+        Some(content_type) => content_type.to_str().ok().and_then(|content_type| content_type.parse::<MediaType>().ok()),
+        None => declared.first().map(|(media_type, _)| *media_type),
+    };
+    let (media_type, schema) = match declared.iter().find(|(media_type, _)| Some(*media_type) == content_type) {
+        Some(found) => *found,
+        None => return Some(problems.response(StatusCode::UNSUPPORTED_MEDIA_TYPE, ProblemType::UnsupportedMediaType,
+            format!("Media type is not supported by this operation, expected one of: {}",
+                declared.iter().map(|(media_type, _)| media_type.as_str()).collect::<Vec<_>>().join(", ")))),
+    };
+
+    let value = match media_type {
+        MediaType::Json => serde_json::from_slice::<Value>(body).map_err(|e| e.to_string()),
+        MediaType::FormUrlEncoded => form::from_bytes::<Value>(body)
+            .map(|value| spec.coerce(schema, value))
+            .map_err(|e| e.to_string()),
+        // XML doesn't map onto the schema without the models' serde attributes
+        MediaType::Xml => return None,
+    };
+    let value = match value {
+        Ok(value) => value,
+        Err(e) => return Some(problems.response(StatusCode::BAD_REQUEST, ProblemType::InvalidBody,
+            format!("Couldn't parse body: {}", e))),
+    };
+
+    let mut violations = Vec::new();
+    spec.validate(schema, &value, "/body", &mut violations);
+    if violations.is_empty() {
+        return None;
+    }
+    Some(problems.violations(StatusCode::UNPROCESSABLE_ENTITY, "Body violates the schema", violations))
+}
+
+/// What `CheckResponses` does when a response violates the API definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnViolation {
+    /// Log the violations as an error, and return the response unchanged
+    Log,
+    /// Panic, failing the test that made the request
+    Panic,
+}
+
+/// A `tower::Layer` that wraps services in `CheckResponses`.
+#[derive(Debug, Clone)]
+pub struct CheckResponsesLayer {
+    spec: Arc<Spec>,
+    on_violation: OnViolation,
+}
+
+impl CheckResponsesLayer {
+    /// Check responses against `spec`, panicking on violations.
+    pub fn new(spec: Spec) -> Self {
+        CheckResponsesLayer {
+            spec: Arc::new(spec),
+            on_violation: OnViolation::Panic,
+        }
+    }
+
+    /// Choose what happens when a response violates the API definition.
+    pub fn on_violation(mut self, on_violation: OnViolation) -> Self {
+        self.on_violation = on_violation;
+        self
+    }
+}
+
+impl<S> Layer<S> for CheckResponsesLayer {
+    type Service = CheckResponses<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CheckResponses {
+            inner,
+            spec: self.spec.clone(),
+            on_violation: self.on_violation,
+        }
+    }
+}
+
+/// Checks the responses of an `Api` implementation against the API definition, to catch
+/// implementations drifting from it in tests. Every response body is buffered, so this
+/// isn't meant for production.
+///
+/// The status code must be declared for the operation, the `Content-Type` must be one
+/// that the response declares, JSON bodies must match their schema and required
+/// headers must be present. Problem details responses, which the `Service` produces for
+/// requests that it rejects and for errors, aren't checked.
+#[derive(Debug, Clone)]
+pub struct CheckResponses<T> {
+    inner: T,
+    spec: Arc<Spec>,
+    on_violation: OnViolation,
+}
+
+impl<T> CheckResponses<T> {
+    /// Check the responses of `inner` against `spec`, panicking on violations.
+    pub fn new(inner: T, spec: Spec) -> Self {
+        CheckResponsesLayer::new(spec).layer(inner)
+    }
+
+    /// Choose what happens when a response violates the API definition.
+    pub fn on_violation(mut self, on_violation: OnViolation) -> Self {
+        self.on_violation = on_violation;
+        self
+    }
+}
+
+impl<T, C> Service<(Request<Body>, C)> for CheckResponses<T>
+where
+    T: Service<(Request<Body>, C), Response = Response<Body>>,
+    T::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = T::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: (Request<Body>, C)) -> Self::Future {
+        let method = request.0.method().clone();
+        let path = request.0.uri().path().to_string();
+        let spec = self.spec.clone();
+        let on_violation = self.on_violation;
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await?;
+            let operation = match spec.find(&operation_method(&method), &path) {
+                Some((operation, _)) => operation,
+                None => return Ok(response),
+            };
+            if is_problem(response.headers()) {
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let mut violations = Vec::new();
+            let body = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(e) => {
+                    violations.push(Violation::new("/body", format!("couldn't be read: {}", e)));
+                    Default::default()
+                },
+            };
+            // HEAD responses have the headers of the GET response, but no body
+            let check_body = method != hyper::Method::HEAD;
+            check_response(&spec, operation, &parts, check_body.then(|| &body[..]), &mut violations);
+
+            if !violations.is_empty() {
+                let violations = violations.iter().map(Violation::to_string).collect::<Vec<_>>().join(", ");
+                let message = format!("{} {} returned a {} response that violates the API definition: {}",
+                    method, path, parts.status.as_u16(), violations);
+                match on_violation {
+                    OnViolation::Log => log::error!("{}", message),
+                    OnViolation::Panic => panic!("{}", message),
+                }
+            }
+
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+/// The method of the operation that serves a request: HEAD is served by GET, as in the
+/// `Service`.
+fn operation_method(method: &hyper::Method) -> hyper::Method {
+    if method == hyper::Method::HEAD {
+        hyper::Method::GET
+    } else {
+        method.clone()
+    }
+}
+
+fn is_problem(headers: &HeaderMap) -> bool {
+    headers.get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+        .map_or(false, |content_type| content_type.essence_str() == crate::problem::PROBLEM_JSON)
+}
+
+/// Add every way in which a response violates its declaration to `violations`. The
+/// body is only checked if it's given.
+fn check_response(spec: &Spec, operation: &Operation, parts: &hyper::http::response::Parts, body: Option<&[u8]>, violations: &mut Vec<Violation>) {
+    let declared = match operation.response(parts.status.as_u16()) {
+        Some(declared) => declared,
+        None => {
+            violations.push(Violation::new("/status", format!("{} is not declared for this operation", parts.status.as_u16())));
+            return;
+        },
+    };
+
+    for (name, required, schema) in &declared.headers {
+        let pointer = format!("/header/{}", name);
+        match parts.headers.get(name.as_str()) {
+            Some(value) => {
+                let value = spec.coerce(schema, Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned()));
+                spec.validate(schema, &value, &pointer, violations);
+            },
+            None if *required => violations.push(Violation::new(pointer, "is required")),
+            None => {},
+        }
+    }
+
+    let body = match body {
+        Some(body) => body,
+        None => return,
+    };
+    if declared.content.is_empty() {
+        if !body.is_empty() {
+            violations.push(Violation::new("/body", "must be empty"));
+        }
+        return;
+    }
+
+    let content_type = parts.headers.get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<MediaType>().ok());
+    let schema = declared.content.iter()
+        .find(|(media_type, _)| media_type.parse::<MediaType>().ok() == content_type && content_type.is_some())
+        .map(|(_, schema)| schema);
+    let schema = match (content_type, schema) {
+        (Some(MediaType::Json), Some(schema)) => schema,
+        // XML doesn't map onto the schema without the models' serde attributes
+        (Some(_), Some(_)) => return,
+        _ => {
+            let declared = declared.content.iter().map(|(media_type, _)| media_type.as_str()).collect::<Vec<_>>().join(", ");
+            violations.push(Violation::new("/header/Content-Type", format!("must be one of {}", declared)));
+            return;
+        },
+    };
+
+    match serde_json::from_slice::<Value>(body) {
+        Ok(value) => spec.validate(schema, &value, "/body", violations),
+        Err(e) => violations.push(Violation::new("/body", format!("must be JSON: {}", e))),
+    }
+}
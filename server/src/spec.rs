@@ -0,0 +1,496 @@
+//! The API definition, loaded at runtime so that traffic can be checked against it
+//! without regenerating code.
+//!
+//! Only the parts of OpenAPI 3.0 that the definition uses are supported: `$ref`s within
+//! the document, parameters in the path, query and headers, and schemas built from
+//! `type`, `format`, `enum`, `required`, `properties`, `items`, `nullable` and the
+//! length, size and range keywords.
+
+use hyper::Method;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::query::Style;
+use crate::validate::{self, Violation};
+
+/// An error loading an API definition.
+#[derive(Debug)]
+pub enum SpecError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file isn't valid YAML
+    Yaml(serde_yaml::Error),
+    /// The document isn't a usable OpenAPI definition
+    Invalid(String),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Io(e) => write!(f, "Couldn't read API definition: {}", e),
+            SpecError::Yaml(e) => write!(f, "Couldn't parse API definition: {}", e),
+            SpecError::Invalid(e) => write!(f, "Invalid API definition: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl From<io::Error> for SpecError {
+    fn from(e: io::Error) -> Self {
+        SpecError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for SpecError {
+    fn from(e: serde_yaml::Error) -> Self {
+        SpecError::Yaml(e)
+    }
+}
+
+/// Where a parameter is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Location {
+    Path,
+    Query,
+    Header,
+}
+
+impl Location {
+    fn from_str(location: &str) -> Option<Self> {
+        match location {
+            "path" => Some(Location::Path),
+            "query" => Some(Location::Query),
+            "header" => Some(Location::Header),
+            _ => None,
+        }
+    }
+
+    /// The first segment of the JSON pointers to parameters in this location.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Location::Path => "path",
+            Location::Query => "query",
+            Location::Header => "header",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Parameter {
+    pub(crate) name: String,
+    pub(crate) location: Location,
+    pub(crate) required: bool,
+    /// How query parameter arrays are delimited; path and header arrays are
+    /// comma-separated
+    pub(crate) style: Style,
+    /// Whether array values are sent as repeated parameters rather than delimited
+    pub(crate) explode: bool,
+    pub(crate) schema: Value,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RequestBody {
+    pub(crate) required: bool,
+    /// Accepted media types, with their schemas
+    pub(crate) content: Vec<(String, Value)>,
+}
+
+/// A response that an operation declares.
+#[derive(Debug, Clone)]
+pub(crate) struct DeclaredResponse {
+    /// Headers, with whether they're required and their schemas
+    pub(crate) headers: Vec<(String, bool, Value)>,
+    /// Media types that the body may be encoded with, with their schemas. Empty if the
+    /// response has no body.
+    pub(crate) content: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Static(String),
+    Param(String),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Operation {
+    pub(crate) method: Method,
+    /// Path template, relative to the base path
+    pub(crate) path: String,
+    segments: Vec<Segment>,
+    pub(crate) parameters: Vec<Parameter>,
+    pub(crate) request_body: Option<RequestBody>,
+    /// Declared responses, keyed by status code, range (`4XX`) or `default`
+    pub(crate) responses: Vec<(String, DeclaredResponse)>,
+}
+
+impl Operation {
+    /// The declared response for a status code, preferring an exact match to a range,
+    /// and a range to the default.
+    pub(crate) fn response(&self, status: u16) -> Option<&DeclaredResponse> {
+        let exact = status.to_string();
+        let range = format!("{}XX", status / 100);
+        [exact.as_str(), range.as_str(), "default"].iter()
+            .find_map(|key| self.responses.iter().find(|(declared, _)| declared.eq_ignore_ascii_case(key)))
+            .map(|(_, response)| response)
+    }
+}
+
+/// A loaded API definition.
+#[derive(Debug, Clone)]
+pub struct Spec {
+    document: Value,
+    base_path: String,
+    operations: Vec<Operation>,
+}
+
+impl Spec {
+    /// Load the API definition from a YAML (or JSON) file, such as `api/openapi.yaml`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// The path that the API is served below, taken from the first of its `servers`.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Serve the API below `base_path` rather than the path of the first server.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path = crate::normalize_base_path(base_path);
+        self
+    }
+
+    /// Find the operation for a request, and the raw values of its path parameters.
+    /// Static path segments take precedence over parameters.
+    pub(crate) fn find<'a, 'p>(&'a self, method: &Method, path: &'p str) -> Option<(&'a Operation, Vec<(&'a str, &'p str)>)> {
+        let path = path.strip_prefix(self.base_path.as_str())?.strip_prefix('/')?;
+        let segments = path.split('/').collect::<Vec<_>>();
+
+        self.operations.iter()
+            .filter(|operation| operation.method == *method && operation.segments.len() == segments.len())
+            .filter_map(|operation| {
+                let mut params = Vec::new();
+                let mut statics = 0;
+                for (segment, value) in operation.segments.iter().zip(&segments) {
+                    match segment {
+                        Segment::Static(name) if name == value => statics += 1,
+                        Segment::Static(_) => return None,
+                        Segment::Param(name) => params.push((name.as_str(), *value)),
+                    }
+                }
+                Some((statics, operation, params))
+            })
+            .max_by_key(|(statics, _, _)| *statics)
+            .map(|(_, operation, params)| (operation, params))
+    }
+
+    /// Follow `$ref`s until reaching a value that isn't one. Unresolvable references
+    /// resolve to an empty schema, which accepts anything.
+    pub(crate) fn resolve<'a>(&'a self, mut value: &'a Value) -> &'a Value {
+        // Bound the number of hops, in case of a reference cycle
+        for _ in 0..32 {
+            let reference = match value.get("$ref").and_then(Value::as_str) {
+                Some(reference) => reference,
+                None => return value,
+            };
+            value = reference.strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer))
+                .unwrap_or(&EMPTY_SCHEMA);
+        }
+        &EMPTY_SCHEMA
+    }
+
+    /// Convert the strings of parameters and form bodies to the types that `schema`
+    /// expects, where they parse. Other values are returned unchanged.
+    pub(crate) fn coerce(&self, schema: &Value, value: Value) -> Value {
+        let schema = self.resolve(schema);
+        match (schema.get("type").and_then(Value::as_str), value) {
+            (Some("integer"), Value::String(s)) => s.parse::<i64>().map(Value::from).unwrap_or(Value::String(s)),
+            (Some("number"), Value::String(s)) => s.parse::<f64>().ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or(Value::String(s)),
+            (Some("boolean"), Value::String(s)) => s.parse::<bool>().map(Value::Bool).unwrap_or(Value::String(s)),
+            (Some("array"), value) => {
+                let items = match value {
+                    Value::Array(items) => items,
+                    // Indexed form keys, e.g. `tags[0][name]`
+                    Value::Object(map) if map.keys().all(|key| key.parse::<usize>().is_ok()) => {
+                        let mut entries = map.into_iter()
+                            .filter_map(|(key, value)| key.parse::<usize>().ok().map(|index| (index, value)))
+                            .collect::<Vec<_>>();
+                        entries.sort_by_key(|(index, _)| *index);
+                        entries.into_iter().map(|(_, value)| value).collect()
+                    },
+                    value @ Value::Object(_) => return value,
+                    value => vec![value],
+                };
+                let item_schema = schema.get("items").unwrap_or(&EMPTY_SCHEMA);
+                Value::Array(items.into_iter().map(|item| self.coerce(item_schema, item)).collect())
+            },
+            (Some("object"), Value::Object(map)) => {
+                Value::Object(map.into_iter().map(|(key, value)| {
+                    let value = match schema.get("properties").and_then(|properties| properties.get(&key)) {
+                        Some(property) => self.coerce(property, value),
+                        None => value,
+                    };
+                    (key, value)
+                }).collect())
+            },
+            (_, value) => value,
+        }
+    }
+
+    /// Add every constraint of `schema` that `value` violates to `violations`, pointing
+    /// below `pointer`.
+    pub(crate) fn validate(&self, schema: &Value, value: &Value, pointer: &str, violations: &mut Vec<Violation>) {
+        let schema = self.resolve(schema);
+
+        if value.is_null() {
+            if !schema.get("nullable").and_then(Value::as_bool).unwrap_or(false) && schema.get("type").is_some() {
+                violations.push(Violation::new(pointer, "must not be null"));
+            }
+            return;
+        }
+
+        if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+            if !has_type(value, expected) {
+                violations.push(Violation::new(pointer, format!("must be of type {}", expected)));
+                return;
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                let allowed = allowed.iter().map(Value::to_string).collect::<Vec<_>>().join(", ");
+                violations.push(Violation::new(pointer, format!("must be one of {}", allowed)));
+            }
+        }
+
+        let limit = |keyword: &str| schema.get(keyword).and_then(Value::as_u64).map(|limit| limit as usize);
+
+        match value {
+            Value::String(s) => {
+                let length = s.chars().count();
+                if let Some(min) = limit("minLength") {
+                    violations.extend(validate::min_length(pointer, s, min).err());
+                }
+                if let Some(max) = limit("maxLength").filter(|max| length > *max) {
+                    violations.push(Violation::new(pointer, format!("must be at most {} characters long", max)));
+                }
+                violations.extend(check_format(schema, s, pointer).err());
+            },
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                    violations.extend(validate::minimum(pointer, n, min).err());
+                }
+                if let Some(max) = schema.get("maximum").and_then(Value::as_f64).filter(|max| n > *max) {
+                    violations.push(Violation::new(pointer, format!("must be at most {}", max)));
+                }
+                if schema.get("format").and_then(Value::as_str) == Some("int32") &&
+                    (n < f64::from(i32::MIN) || n > f64::from(i32::MAX)) {
+                    violations.push(Violation::new(pointer, "must be a 32-bit integer"));
+                }
+            },
+            Value::Array(items) => {
+                if let Some(min) = limit("minItems") {
+                    violations.extend(validate::min_items(pointer, items, min).err());
+                }
+                if let Some(max) = limit("maxItems").filter(|max| items.len() > *max) {
+                    violations.push(Violation::new(pointer, format!("must have at most {} items", max)));
+                }
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        self.validate(item_schema, item, &format!("{}/{}", pointer, index), violations);
+                    }
+                }
+            },
+            Value::Object(map) => {
+                for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                    if !map.contains_key(name) {
+                        violations.push(Violation::new(format!("{}/{}", pointer, name), "is required"));
+                    }
+                }
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, value) in map {
+                        if let Some(property) = properties.get(name) {
+                            self.validate(property, value, &format!("{}/{}", pointer, name), violations);
+                        }
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+impl FromStr for Spec {
+    type Err = SpecError;
+
+    /// Parse an API definition from YAML (or JSON).
+    fn from_str(document: &str) -> Result<Self, Self::Err> {
+        let document: Value = serde_yaml::from_str(document)?;
+
+        let base_path = document.pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .map(|server| match url::Url::parse(server) {
+                Ok(url) => url.path().to_string(),
+                // A relative URL is the path itself
+                Err(_) => server.to_string(),
+            })
+            .unwrap_or_default();
+
+        let mut spec = Spec {
+            base_path: crate::normalize_base_path(&base_path),
+            document,
+            operations: Vec::new(),
+        };
+        spec.operations = spec.parse_operations()?;
+        Ok(spec)
+    }
+}
+
+impl Spec {
+    fn parse_operations(&self) -> Result<Vec<Operation>, SpecError> {
+        let paths = self.document.get("paths")
+            .and_then(Value::as_object)
+            .ok_or_else(|| SpecError::Invalid("no paths".to_string()))?;
+
+        let mut operations = Vec::new();
+        for (path, item) in paths {
+            let item = self.resolve(item);
+            let segments = path.trim_start_matches('/').split('/').map(|segment| {
+                match segment.strip_prefix('{').and_then(|segment| segment.strip_suffix('}')) {
+                    Some(name) => Segment::Param(name.to_string()),
+                    None => Segment::Static(segment.to_string()),
+                }
+            }).collect::<Vec<_>>();
+
+            for (method, operation) in item.as_object().into_iter().flatten() {
+                // Path items also hold `parameters`, `summary` and so on
+                let method = match method.as_str() {
+                    "get" => Method::GET,
+                    "put" => Method::PUT,
+                    "post" => Method::POST,
+                    "delete" => Method::DELETE,
+                    "options" => Method::OPTIONS,
+                    "head" => Method::HEAD,
+                    "patch" => Method::PATCH,
+                    "trace" => Method::TRACE,
+                    _ => continue,
+                };
+
+                // Operation parameters override path item parameters with the same name and location
+                let mut parameters = self.parse_parameters(operation.get("parameters"))?;
+                for parameter in self.parse_parameters(item.get("parameters"))? {
+                    if !parameters.iter().any(|p| p.name == parameter.name && p.location == parameter.location) {
+                        parameters.push(parameter);
+                    }
+                }
+
+                operations.push(Operation {
+                    method,
+                    path: path.clone(),
+                    segments: segments.clone(),
+                    parameters,
+                    request_body: operation.get("requestBody").map(|body| self.parse_request_body(body)),
+                    responses: operation.get("responses").and_then(Value::as_object).into_iter().flatten()
+                        .map(|(status, response)| (status.clone(), self.parse_response(response)))
+                        .collect(),
+                });
+            }
+        }
+        Ok(operations)
+    }
+
+    fn parse_parameters(&self, parameters: Option<&Value>) -> Result<Vec<Parameter>, SpecError> {
+        let mut parsed = Vec::new();
+        for parameter in parameters.and_then(Value::as_array).into_iter().flatten() {
+            let parameter = self.resolve(parameter);
+            let name = parameter.get("name").and_then(Value::as_str)
+                .ok_or_else(|| SpecError::Invalid("parameter without a name".to_string()))?;
+            // Cookie parameters aren't checked
+            let location = match parameter.get("in").and_then(Value::as_str).and_then(Location::from_str) {
+                Some(location) => location,
+                None => continue,
+            };
+            parsed.push(Parameter {
+                name: name.to_string(),
+                location,
+                required: parameter.get("required").and_then(Value::as_bool).unwrap_or(location == Location::Path),
+                style: parameter.get("style").and_then(Value::as_str).and_then(|style| style.parse().ok()).unwrap_or(Style::Form),
+                explode: parameter.get("explode").and_then(Value::as_bool).unwrap_or(location == Location::Query),
+                schema: parameter.get("schema").cloned().unwrap_or_else(|| EMPTY_SCHEMA.clone()),
+            });
+        }
+        Ok(parsed)
+    }
+
+    fn parse_request_body(&self, body: &Value) -> RequestBody {
+        let body = self.resolve(body);
+        RequestBody {
+            required: body.get("required").and_then(Value::as_bool).unwrap_or(false),
+            content: self.parse_content(body),
+        }
+    }
+
+    fn parse_response(&self, response: &Value) -> DeclaredResponse {
+        let response = self.resolve(response);
+        DeclaredResponse {
+            headers: response.get("headers").and_then(Value::as_object).into_iter().flatten()
+                .map(|(name, header)| {
+                    let header = self.resolve(header);
+                    let required = header.get("required").and_then(Value::as_bool).unwrap_or(false);
+                    let schema = header.get("schema").cloned().unwrap_or_else(|| EMPTY_SCHEMA.clone());
+                    (name.clone(), required, schema)
+                })
+                .collect(),
+            content: self.parse_content(response),
+        }
+    }
+
+    /// The media types of a request body or response's `content`, with their schemas.
+    fn parse_content(&self, value: &Value) -> Vec<(String, Value)> {
+        value.get("content").and_then(Value::as_object).into_iter().flatten()
+            .map(|(media_type, content)| {
+                let schema = content.get("schema").cloned().unwrap_or_else(|| EMPTY_SCHEMA.clone());
+                (media_type.clone(), schema)
+            })
+            .collect()
+    }
+}
+
+static EMPTY_SCHEMA: Value = Value::Null;
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// Check the formats that have a meaning beyond their type. Other formats, and those
+/// that only affect numbers, are accepted.
+fn check_format(schema: &Value, value: &str, pointer: &str) -> Result<(), Violation> {
+    match schema.get("format").and_then(Value::as_str) {
+        Some("date-time") => chrono::DateTime::parse_from_rfc3339(value)
+            .map(|_| ())
+            .map_err(|_| Violation::new(pointer, "must be an RFC 3339 date-time")),
+        Some("date") => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(|_| ())
+            .map_err(|_| Violation::new(pointer, "must be a date")),
+        Some("email") => validate::email(pointer, value),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,112 @@
+//! Validation of request data against the constraints in the API definition that
+//! deserialization doesn't enforce, such as minimum lengths and values.
+//!
+//! The models implement `Validate` for the constraints that their schemas declare. The
+//! checks for individual constraints are also used by `spec`, which reads them from the
+//! API definition at run time.
+
+use std::fmt;
+
+/// A schema constraint that a value violates.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Violation {
+    /// JSON pointer to the value, e.g. `/tags/0/name`
+    pub pointer: String,
+
+    /// Which constraint the value violates
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Violation {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pointer, self.message)
+    }
+}
+
+/// Checks a value against the constraints of its schema.
+pub trait Validate {
+    /// Add every constraint that this value violates to `violations`, pointing below
+    /// `pointer` - the JSON pointer to this value.
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>);
+
+    /// Check this value, returning every constraint that it violates.
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        self.validate_at("", &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+        if let Some(value) = self {
+            value.validate_at(pointer, violations);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+        for (index, item) in self.iter().enumerate() {
+            item.validate_at(&format!("{}/{}", pointer, index), violations);
+        }
+    }
+}
+
+/// Check a `minLength` constraint, counting characters.
+pub(crate) fn min_length(pointer: &str, value: &str, min: usize) -> Result<(), Violation> {
+    match value.chars().count() {
+        0 if min == 1 => Err(Violation::new(pointer, "must not be empty")),
+        length if length < min => Err(Violation::new(pointer, format!("must be at least {} characters long", min))),
+        _ => Ok(()),
+    }
+}
+
+/// Check a `minItems` constraint.
+pub(crate) fn min_items<T>(pointer: &str, value: &[T], min: usize) -> Result<(), Violation> {
+    match value.len() {
+        0 if min == 1 => Err(Violation::new(pointer, "must not be empty")),
+        length if length < min => Err(Violation::new(pointer, format!("must have at least {} items", min))),
+        _ => Ok(()),
+    }
+}
+
+/// Check a `minimum` constraint.
+pub(crate) fn minimum<T: PartialOrd + fmt::Display>(pointer: &str, value: T, min: T) -> Result<(), Violation> {
+    if value < min {
+        return Err(Violation::new(pointer, format!("must be at least {}", min)));
+    }
+    Ok(())
+}
+
+/// Check the `email` format: a local part and a domain with at least two labels, with
+/// no whitespace.
+pub(crate) fn email(pointer: &str, value: &str) -> Result<(), Violation> {
+    let valid = match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() &&
+                !domain.contains('@') &&
+                domain.split('.').count() >= 2 &&
+                domain.split('.').all(|label| !label.is_empty()) &&
+                !value.chars().any(char::is_whitespace)
+        },
+        None => false,
+    };
+    if !valid {
+        return Err(Violation::new(pointer, "must be an email address"));
+    }
+    Ok(())
+}
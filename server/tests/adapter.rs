@@ -0,0 +1,62 @@
+//! `ApiService` serves a configured `Service` from inside another application.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::Recorder;
+use hyper::service::Service as _;
+use hyper::{Body, Request, StatusCode};
+use openapi_client::server::{ApiService, Service};
+use swagger::auth::{Authorization, Scopes};
+use swagger::EmptyContext;
+
+fn authorization() -> Authorization {
+    Authorization {
+        subject: "alice".to_string(),
+        scopes: Scopes::All,
+        issuer: None,
+    }
+}
+
+#[tokio::test]
+async fn requests_are_delegated_to_the_service() {
+    let api = Recorder::default();
+    let mut service = ApiService::<_, EmptyContext>::new(Service::new(api.clone()).with_base_path("/v3"));
+    let mut request = Request::get("/v3/store/inventory").body(Body::empty()).unwrap();
+    request.extensions_mut().insert(authorization());
+
+    let response = service.call(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(api.calls(), vec!["GetInventory"]);
+}
+
+#[tokio::test]
+async fn prefix_is_stripped_once() {
+    let api = Recorder::default();
+    let mut service = ApiService::<_, EmptyContext>::new(Service::new(api.clone())).with_prefix("/petstore");
+
+    let mut request = Request::get("/petstore/api/v3/store/inventory").body(Body::empty()).unwrap();
+    request.extensions_mut().insert(authorization());
+    service.call(request).await.unwrap();
+    assert_eq!(api.calls(), vec!["GetInventory"]);
+
+    // A path that an outer router has already stripped is routed as it is
+    let mut request = Request::get("/api/v3/store/inventory").body(Body::empty()).unwrap();
+    request.extensions_mut().insert(authorization());
+    service.call(request).await.unwrap();
+    assert_eq!(api.calls(), vec!["GetInventory", "GetInventory"]);
+}
+
+#[tokio::test]
+async fn requests_without_credentials_are_rejected() {
+    let api = Recorder::default();
+    let mut service = ApiService::<_, EmptyContext>::new(Service::new(api.clone()));
+    let request = Request::get("/api/v3/store/inventory").body(Body::empty()).unwrap();
+
+    let response = service.call(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(api.calls().is_empty());
+}
@@ -0,0 +1,147 @@
+//! An `Api` implementation and context shared by the integration tests.
+
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use openapi_client::*;
+use std::sync::{Arc, Mutex};
+use swagger::auth::{AuthData, Authorization, Scopes};
+use swagger::{ApiError, ContextBuilder, EmptyContext, Push, XSpanIdString};
+
+/// The context that the server passes to the `Api`.
+pub type ServerContext = swagger::make_context_ty!(ContextBuilder, EmptyContext, Option<AuthData>, Option<Authorization>, XSpanIdString);
+
+/// A context for a request authorized as `subject`, with every scope.
+pub fn authorized(subject: &str) -> ServerContext {
+    with_scopes(subject, Scopes::All)
+}
+
+/// A context for a request authorized as `subject`, with `scopes`.
+pub fn with_scopes(subject: &str, scopes: Scopes) -> ServerContext {
+    let authorization = Authorization {
+        subject: subject.to_string(),
+        scopes,
+        issuer: None,
+    };
+    swagger::make_context!(ContextBuilder, EmptyContext, None::<AuthData>, Some(authorization), XSpanIdString::default())
+}
+
+/// A context for a request without credentials.
+pub fn unauthenticated() -> ServerContext {
+    swagger::make_context!(ContextBuilder, EmptyContext, None::<AuthData>, None::<Authorization>, XSpanIdString::default())
+}
+
+/// Records the operations that it's called for, and fails them all.
+#[derive(Clone, Default)]
+pub struct Recorder {
+    calls: Arc<Mutex<Vec<&'static str>>>,
+    partial_lists: bool,
+}
+
+impl Recorder {
+    /// A recorder whose `create_users_with_list_input_stream` takes the users before an
+    /// invalid one and answers with a 202, rather than failing.
+    pub fn accepting_partial_lists() -> Self {
+        Recorder {
+            partial_lists: true,
+            ..Recorder::default()
+        }
+    }
+
+    /// The operations called so far.
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record<T>(&self, operation_id: &'static str) -> Result<T, ApiError> {
+        self.calls.lock().unwrap().push(operation_id);
+        Err(ApiError(format!("{} is not implemented", operation_id)))
+    }
+}
+
+#[async_trait]
+impl<C> Api<C> for Recorder where C: Send + Sync {
+    async fn add_pet(&self, _pet: models::Pet, _context: &C) -> Result<AddPetResponse, ApiError> {
+        self.record("AddPet")
+    }
+
+    async fn delete_pet(&self, _pet_id: i64, _api_key: Option<String>, _context: &C) -> Result<DeletePetResponse, ApiError> {
+        self.record("DeletePet")
+    }
+
+    async fn find_pets_by_status(&self, _status: Option<models::PetStatus>, _context: &C) -> Result<FindPetsByStatusResponse, ApiError> {
+        self.record("FindPetsByStatus")
+    }
+
+    async fn find_pets_by_tags(&self, _tags: Option<&Vec<String>>, _context: &C) -> Result<FindPetsByTagsResponse, ApiError> {
+        self.record("FindPetsByTags")
+    }
+
+    async fn get_pet_by_id(&self, _pet_id: i64, _context: &C) -> Result<GetPetByIdResponse, ApiError> {
+        self.record("GetPetById")
+    }
+
+    async fn update_pet(&self, _pet: models::Pet, _context: &C) -> Result<UpdatePetResponse, ApiError> {
+        self.record("UpdatePet")
+    }
+
+    async fn update_pet_with_form(&self, _pet_id: i64, _name: Option<String>, _status: Option<String>, _context: &C) -> Result<UpdatePetWithFormResponse, ApiError> {
+        self.record("UpdatePetWithForm")
+    }
+
+    async fn delete_order(&self, _order_id: i64, _context: &C) -> Result<DeleteOrderResponse, ApiError> {
+        self.record("DeleteOrder")
+    }
+
+    async fn get_inventory(&self, _context: &C) -> Result<GetInventoryResponse, ApiError> {
+        self.record("GetInventory")
+    }
+
+    async fn get_order_by_id(&self, _order_id: i64, _context: &C) -> Result<GetOrderByIdResponse, ApiError> {
+        self.record("GetOrderById")
+    }
+
+    async fn place_order(&self, _order: Option<models::Order>, _context: &C) -> Result<PlaceOrderResponse, ApiError> {
+        self.record("PlaceOrder")
+    }
+
+    async fn create_user(&self, _user: Option<models::User>, _context: &C) -> Result<CreateUserResponse, ApiError> {
+        self.record("CreateUser")
+    }
+
+    async fn create_users_with_list_input(&self, _user: Option<&Vec<models::User>>, _context: &C) -> Result<CreateUsersWithListInputResponse, ApiError> {
+        self.record("CreateUsersWithListInput")
+    }
+
+    async fn create_users_with_list_input_stream(&self, user: Option<UserStream>, context: &C) -> Result<CreateUsersWithListInputResponse, ApiError> {
+        let mut user = match user {
+            Some(user) if self.partial_lists => user,
+            Some(user) => return self.create_users_with_list_input(Some(&user.try_collect().await?), context).await,
+            None => return self.create_users_with_list_input(None, context).await,
+        };
+        self.calls.lock().unwrap().push("CreateUsersWithListInput");
+        while let Some(Ok(_)) = user.next().await {}
+        Ok(CreateUsersWithListInputResponse::SuccessfulOperation_2 { status: 202 })
+    }
+
+    async fn delete_user(&self, _username: String, _context: &C) -> Result<DeleteUserResponse, ApiError> {
+        self.record("DeleteUser")
+    }
+
+    async fn get_user_by_name(&self, _username: String, _context: &C) -> Result<GetUserByNameResponse, ApiError> {
+        self.record("GetUserByName")
+    }
+
+    async fn login_user(&self, _username: Option<String>, _password: Option<String>, _context: &C) -> Result<LoginUserResponse, ApiError> {
+        self.record("LoginUser")
+    }
+
+    async fn logout_user(&self, _context: &C) -> Result<LogoutUserResponse, ApiError> {
+        self.record("LogoutUser")
+    }
+
+    async fn update_user(&self, _username: String, _user: Option<models::User>, _context: &C) -> Result<UpdateUserResponse, ApiError> {
+        self.record("UpdateUser")
+    }
+}
@@ -0,0 +1,125 @@
+//! Parameters and request bodies that violate the API definition's constraints are
+//! refused with a problem listing each violation by JSON pointer.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::{authorized, Recorder};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::Service as _;
+use hyper::{Body, Method, Request, StatusCode};
+use openapi_client::server::Service;
+use openapi_client::BASE_PATH;
+use serde_json::{json, Value};
+
+/// Send a request to a fresh `Service`, returning the response's status and body, and the
+/// operations that reached the `Api`.
+async fn send(method: Method, path: &str, body: Option<Value>) -> (StatusCode, Value, Vec<&'static str>) {
+    let api = Recorder::default();
+    let request = Request::builder()
+        .method(method)
+        .uri(format!("{}{}", BASE_PATH, path))
+        .header(CONTENT_TYPE, "application/json");
+    let request = match body {
+        Some(body) => request.body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }.unwrap();
+
+    let response = Service::new(api.clone()).call((request, authorized("alice"))).await.unwrap();
+
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let mut body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    // The instance is the request's span ID, which is different every time
+    if let Some(problem) = body.as_object_mut() {
+        problem.remove("instance");
+    }
+    (status, body, api.calls())
+}
+
+/// The problem that a request to `operation_id` violating `violations` gets.
+fn problem(operation_id: &str, status: StatusCode, detail: &str, violations: &[(&str, &str)]) -> Value {
+    json!({
+        "operationId": operation_id,
+        "type": "urn:problem-type:petstore:constraint-violation",
+        "title": "Constraint violation",
+        "status": status.as_u16(),
+        "detail": detail,
+        "violations": violations.iter()
+            .map(|(pointer, message)| json!({ "pointer": pointer, "message": message }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[tokio::test]
+async fn pets_need_a_name_and_a_photo() {
+    let pet = json!({ "name": "", "photoUrls": [] });
+
+    for (method, operation_id) in &[(Method::POST, "AddPet"), (Method::PUT, "UpdatePet")] {
+        let (status, body, calls) = send(method.clone(), "/pet", Some(pet.clone())).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body, problem(operation_id, status, "Body parameter Pet violates the schema",
+            &[("/name", "must not be empty"), ("/photoUrls", "must not be empty")]));
+        assert!(calls.is_empty(), "{:?}", calls);
+    }
+
+    let (_, _, calls) = send(Method::POST, "/pet", Some(json!({ "name": "doggie", "photoUrls": ["https://example.com/doggie.png"] }))).await;
+    assert_eq!(calls, vec!["AddPet"]);
+}
+
+#[tokio::test]
+async fn orders_are_for_at_least_one_pet() {
+    let (status, body, calls) = send(Method::POST, "/store/order", Some(json!({ "petId": 1, "quantity": 0 }))).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body, problem("PlaceOrder", status, "Body parameter Order violates the schema", &[("/quantity", "must be at least 1")]));
+    assert!(calls.is_empty(), "{:?}", calls);
+
+    let (_, _, calls) = send(Method::POST, "/store/order", Some(json!({ "petId": 1, "quantity": 1 }))).await;
+    assert_eq!(calls, vec!["PlaceOrder"]);
+}
+
+#[tokio::test]
+async fn users_need_a_valid_email_address() {
+    for email in &["", "john", "john@localhost", "john smith@email.com"] {
+        let (status, body, calls) = send(Method::POST, "/user", Some(json!({ "username": "john", "email": email }))).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", email);
+        assert_eq!(body["violations"][0]["pointer"], "/email", "{}", email);
+        assert!(calls.is_empty(), "{:?}", calls);
+    }
+
+    let (_, _, calls) = send(Method::POST, "/user", Some(json!({ "username": "john", "email": "john@email.com" }))).await;
+    assert_eq!(calls, vec!["CreateUser"]);
+}
+
+#[tokio::test]
+async fn violations_in_lists_point_at_the_item() {
+    let users = json!([{ "username": "john", "email": "john@email.com" }, { "username": "jane", "email": "jane" }]);
+
+    let (status, body, _) = send(Method::POST, "/user/createWithList", Some(users)).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["violations"][0]["pointer"], "/1/email");
+}
+
+#[tokio::test]
+async fn ids_must_not_be_negative() {
+    for (method, path, operation_id, detail, pointer) in &[
+        (Method::GET, "/pet/-1", "GetPetById", "Path parameter petId violates the schema", "/petId"),
+        (Method::DELETE, "/pet/-1", "DeletePet", "Path parameter petId violates the schema", "/petId"),
+        (Method::GET, "/store/order/-1", "GetOrderById", "Path parameter orderId violates the schema", "/orderId"),
+        (Method::DELETE, "/store/order/-1", "DeleteOrder", "Path parameter orderId violates the schema", "/orderId"),
+    ] {
+        let (status, body, calls) = send(method.clone(), path, None).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} {}", method, path);
+        assert_eq!(body, problem(operation_id, status, detail, &[(pointer, "must be at least 0")]));
+        assert!(calls.is_empty(), "{:?}", calls);
+    }
+
+    let (_, _, calls) = send(Method::GET, "/pet/0", None).await;
+    assert_eq!(calls, vec!["GetPetById"]);
+}
@@ -0,0 +1,137 @@
+//! The client sends the context's credentials to the operations that declare a scheme
+//! for them.
+
+#![cfg(feature = "client")]
+
+use futures::future;
+use hyper::header::AUTHORIZATION;
+use hyper::service::Service;
+use hyper::{Body, HeaderMap, Request, Response};
+use openapi_client::{Api, Client};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use swagger::auth::AuthData;
+use swagger::{ContextBuilder, EmptyContext, Push, XSpanIdString};
+
+type ClientContext = swagger::make_context_ty!(ContextBuilder, EmptyContext, Option<AuthData>, XSpanIdString);
+
+fn context(auth_data: Option<AuthData>) -> ClientContext {
+    swagger::make_context!(ContextBuilder, EmptyContext, auth_data, XSpanIdString::default())
+}
+
+/// Records the headers of each request, and answers it with an empty 200.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<HeaderMap>>>);
+
+impl Capture {
+    /// The headers of the only request sent.
+    fn headers(&self) -> HeaderMap {
+        let requests = self.0.lock().unwrap();
+        assert_eq!(requests.len(), 1, "Expected one request");
+        requests[0].clone()
+    }
+}
+
+impl<C> Service<(Request<Body>, C)> for Capture {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, (request, _context): (Request<Body>, C)) -> Self::Future {
+        self.0.lock().unwrap().push(request.headers().clone());
+        future::ok(Response::new(Body::empty()))
+    }
+}
+
+fn client(capture: &Capture) -> Client<Capture, ClientContext> {
+    Client::try_new_with_client_service(capture.clone(), "http://localhost").unwrap()
+}
+
+#[tokio::test]
+async fn logout_sends_the_session_as_an_api_key() {
+    let capture = Capture::default();
+
+    client(&capture).logout_user(&context(Some(AuthData::ApiKey("session-token".to_string())))).await.unwrap();
+
+    let headers = capture.headers();
+    assert_eq!(headers["api_key"], "session-token");
+    assert!(headers.get(AUTHORIZATION).is_none());
+}
+
+#[tokio::test]
+async fn logout_sends_the_session_as_a_bearer_token() {
+    let capture = Capture::default();
+
+    client(&capture).logout_user(&context(Some(AuthData::bearer("session-token")))).await.unwrap();
+
+    let headers = capture.headers();
+    assert_eq!(headers[AUTHORIZATION], "Bearer session-token");
+    assert!(headers.get("api_key").is_none());
+}
+
+fn api_key() -> ClientContext {
+    context(Some(AuthData::ApiKey("special-key".to_string())))
+}
+
+// The responses are empty, so the operations may fail - only the requests matter here
+
+#[tokio::test]
+async fn api_keys_go_in_the_api_key_header() {
+    let capture = Capture::default();
+    let _ = client(&capture).get_inventory(&api_key()).await;
+
+    let headers = capture.headers();
+    assert_eq!(headers["api_key"], "special-key");
+    assert!(headers.get(AUTHORIZATION).is_none());
+
+    let capture = Capture::default();
+    let _ = client(&capture).get_pet_by_id(1, &api_key()).await;
+
+    assert_eq!(capture.headers()["api_key"], "special-key");
+}
+
+#[tokio::test]
+async fn api_keys_are_not_sent_to_bearer_only_operations() {
+    let capture = Capture::default();
+    let _ = client(&capture).find_pets_by_status(None, &api_key()).await;
+
+    let headers = capture.headers();
+    assert!(headers.get("api_key").is_none());
+    assert!(headers.get(AUTHORIZATION).is_none());
+
+    // deletePet's own api_key header parameter isn't the context's API key
+    let capture = Capture::default();
+    let _ = client(&capture).delete_pet(1, None, &api_key()).await;
+
+    assert!(capture.headers().get("api_key").is_none());
+}
+
+#[tokio::test]
+async fn bearer_tokens_go_in_the_authorization_header() {
+    let capture = Capture::default();
+    let _ = client(&capture).get_pet_by_id(1, &context(Some(AuthData::bearer("token")))).await;
+
+    let headers = capture.headers();
+    assert_eq!(headers[AUTHORIZATION], "Bearer token");
+    assert!(headers.get("api_key").is_none());
+
+    let capture = Capture::default();
+    let _ = client(&capture).get_inventory(&context(Some(AuthData::bearer("token")))).await;
+
+    assert!(capture.headers().get(AUTHORIZATION).is_none());
+}
+
+#[tokio::test]
+async fn no_credentials_are_sent_to_operations_without_security() {
+    let capture = Capture::default();
+    let _ = client(&capture).login_user(Some("alice".to_string()), Some("secret".to_string()), &api_key()).await;
+
+    let headers = capture.headers();
+    assert!(headers.get("api_key").is_none());
+    assert!(headers.get(AUTHORIZATION).is_none());
+}
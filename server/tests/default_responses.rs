@@ -0,0 +1,62 @@
+//! The client reads any status that an operation doesn't declare as its default response.
+
+#![cfg(feature = "client")]
+
+use futures::future;
+use hyper::service::Service;
+use hyper::{Body, Request, Response, StatusCode};
+use openapi_client::{Api, Client, CreateUsersWithListInputResponse, LogoutUserResponse, UpdateUserResponse};
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+use swagger::auth::AuthData;
+use swagger::{ContextBuilder, EmptyContext, Push, XSpanIdString};
+
+type ClientContext = swagger::make_context_ty!(ContextBuilder, EmptyContext, Option<AuthData>, XSpanIdString);
+
+fn context() -> ClientContext {
+    swagger::make_context!(ContextBuilder, EmptyContext, Some(AuthData::ApiKey("session-token".to_string())), XSpanIdString::default())
+}
+
+/// Answers every request with an empty response with the given status.
+#[derive(Clone, Copy)]
+struct Status(StatusCode);
+
+impl<C> Service<(Request<Body>, C)> for Status {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: (Request<Body>, C)) -> Self::Future {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = self.0;
+        future::ok(response)
+    }
+}
+
+fn client(status: StatusCode) -> Client<Status, ClientContext> {
+    Client::try_new_with_client_service(Status(status), "http://localhost").unwrap()
+}
+
+#[tokio::test]
+async fn undeclared_statuses_are_default_responses() {
+    for status in &[StatusCode::NO_CONTENT, StatusCode::NOT_FOUND, StatusCode::SERVICE_UNAVAILABLE] {
+        let response = client(*status).logout_user(&context()).await.unwrap();
+        assert_eq!(response, LogoutUserResponse::SuccessfulOperation { status: status.as_u16() });
+
+        let response = client(*status).update_user("alice".to_string(), None, &context()).await.unwrap();
+        assert_eq!(response, UpdateUserResponse::SuccessfulOperation { status: status.as_u16() });
+    }
+}
+
+#[tokio::test]
+async fn declared_statuses_keep_their_own_response() {
+    let response = client(StatusCode::CREATED).create_users_with_list_input(None, &context()).await.unwrap();
+    assert_eq!(response, CreateUsersWithListInputResponse::SuccessfulOperation_2 { status: 201 });
+
+    // 200 is declared with a body, so an empty one doesn't fall back to the default
+    assert!(client(StatusCode::OK).create_users_with_list_input(None, &context()).await.is_err());
+}
@@ -0,0 +1,264 @@
+//! `JwtValidator` checks bearer tokens, and `Authenticator` rejects the ones it refuses.
+//!
+//! The keys in `tests/fixtures/jwt` were generated for these tests alone: `rs256.key` and
+//! `es256.key` sign tokens that `rs256.pub.pem`, `es256.pub.pem` and `jwks.json` verify,
+//! and `other-rs256.key` signs tokens that only `other-rs256.pub.pem` verifies.
+
+#![cfg(feature = "jwt")]
+
+mod common;
+
+use common::ServerContext;
+use futures::future;
+use hyper::header::WWW_AUTHENTICATE;
+use hyper::service::Service;
+use hyper::{Body, Request, Response, StatusCode};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use openapi_client::server::auth::{AuthenticatorLayer, InMemoryKeyStore, TokenError, TokenValidator};
+use openapi_client::server::jwt::{JwtKey, JwtValidator};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+use swagger::auth::{AuthData, Authorization, Bearer, Scopes};
+use swagger::{EmptyContext, Has, Push, XSpanIdString};
+use tower::Layer;
+
+const SECRET: &str = "a-shared-secret";
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/jwt/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Claims for `cosmo`, valid for the next hour.
+fn claims() -> Value {
+    json!({
+        "sub": "cosmo",
+        "iss": "https://auth.example.com",
+        "aud": "petstore",
+        "exp": now() + 3600,
+        "scope": "read:pets write:pets",
+    })
+}
+
+fn sign(algorithm: Algorithm, kid: Option<&str>, key: &EncodingKey, claims: &Value) -> String {
+    let mut header = Header::new(algorithm);
+    header.kid = kid.map(str::to_string);
+    jsonwebtoken::encode(&header, claims, key).unwrap()
+}
+
+fn hs256(claims: &Value) -> String {
+    sign(Algorithm::HS256, None, &EncodingKey::from_secret(SECRET.as_bytes()), claims)
+}
+
+fn rs256(key: &str, kid: Option<&str>, claims: &Value) -> String {
+    let key = EncodingKey::from_rsa_pem(&fs::read(fixture(key)).unwrap()).unwrap();
+    sign(Algorithm::RS256, kid, &key, claims)
+}
+
+fn es256(kid: Option<&str>, claims: &Value) -> String {
+    let key = EncodingKey::from_ec_pem(&fs::read(fixture("es256.key")).unwrap()).unwrap();
+    sign(Algorithm::ES256, kid, &key, claims)
+}
+
+fn scopes(scopes: &[&str]) -> Scopes {
+    Scopes::Some(scopes.iter().map(|scope| scope.to_string()).collect::<BTreeSet<_>>())
+}
+
+fn rejection(result: Result<Authorization, TokenError>) -> String {
+    result.expect_err("Token should be rejected").0
+}
+
+#[test]
+fn valid_tokens_are_authorized_with_their_scopes() {
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET))
+        .with_key(JwtKey::rs256_pem(fixture("rs256.pub.pem")).unwrap())
+        .with_key(JwtKey::es256_pem(fixture("es256.pub.pem")).unwrap());
+
+    for token in &[hs256(&claims()), rs256("rs256.key", None, &claims()), es256(None, &claims())] {
+        let authorization = validator.validate(token).unwrap();
+        assert_eq!(authorization.subject, "cosmo");
+        assert_eq!(authorization.issuer.as_deref(), Some("https://auth.example.com"));
+        assert_eq!(authorization.scopes, scopes(&["read:pets", "write:pets"]));
+    }
+}
+
+#[test]
+fn tokens_without_a_scope_claim_have_no_scopes() {
+    let mut claims = claims();
+    claims.as_object_mut().unwrap().remove("scope");
+
+    let authorization = JwtValidator::new(JwtKey::hs256(SECRET)).validate(&hs256(&claims)).unwrap();
+
+    assert_eq!(authorization.scopes, scopes(&[]));
+}
+
+#[test]
+fn expired_tokens_are_rejected() {
+    let mut claims = claims();
+    claims["exp"] = json!(now() - 60);
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET));
+
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token has expired");
+    assert!(validator.with_leeway(120).validate(&hs256(&claims)).is_ok());
+}
+
+#[test]
+fn tokens_are_rejected_before_nbf() {
+    let mut claims = claims();
+    claims["nbf"] = json!(now() + 60);
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET));
+
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token is not valid yet");
+    assert!(validator.with_leeway(120).validate(&hs256(&claims)).is_ok());
+}
+
+#[test]
+fn tokens_without_exp_are_rejected() {
+    let mut claims = claims();
+    claims.as_object_mut().unwrap().remove("exp");
+
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET));
+
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token has no exp claim");
+}
+
+#[test]
+fn tokens_from_other_issuers_are_rejected() {
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET)).with_issuer("https://auth.example.com");
+    assert!(validator.validate(&hs256(&claims())).is_ok());
+
+    let mut claims = claims();
+    claims["iss"] = json!("https://evil.example.com");
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token has the wrong issuer");
+
+    claims.as_object_mut().unwrap().remove("iss");
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token has no iss claim");
+}
+
+#[test]
+fn tokens_for_other_audiences_are_rejected() {
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET)).with_audience("petstore");
+    assert!(validator.validate(&hs256(&claims())).is_ok());
+
+    let mut claims = claims();
+    claims["aud"] = json!("bookstore");
+    assert_eq!(rejection(validator.validate(&hs256(&claims))), "Token has the wrong audience");
+}
+
+#[test]
+fn tokens_signed_with_other_keys_are_rejected() {
+    let validator = JwtValidator::new(JwtKey::rs256_pem(fixture("rs256.pub.pem")).unwrap());
+    assert_eq!(rejection(validator.validate(&rs256("other-rs256.key", None, &claims()))), "Token signature is invalid");
+
+    let validator = JwtValidator::new(JwtKey::hs256("another-secret"));
+    assert_eq!(rejection(validator.validate(&hs256(&claims()))), "Token signature is invalid");
+}
+
+#[test]
+fn tokens_signed_with_other_algorithms_are_rejected() {
+    let validator = JwtValidator::new(JwtKey::rs256_pem(fixture("rs256.pub.pem")).unwrap());
+
+    assert_eq!(rejection(validator.validate(&es256(None, &claims()))), "No key accepts ES256 tokens");
+    assert_eq!(rejection(validator.validate(&hs256(&claims()))), "No key accepts HS256 tokens");
+}
+
+#[test]
+fn any_configured_key_may_have_signed_a_token() {
+    let validator = JwtValidator::new(JwtKey::rs256_pem(fixture("other-rs256.pub.pem")).unwrap())
+        .with_key(JwtKey::rs256_pem(fixture("rs256.pub.pem")).unwrap());
+
+    assert!(validator.validate(&rs256("rs256.key", None, &claims())).is_ok());
+    assert!(validator.validate(&rs256("other-rs256.key", None, &claims())).is_ok());
+}
+
+#[test]
+fn jwk_sets_provide_a_key_for_each_algorithm() {
+    let keys = JwtKey::jwk_set(fixture("jwks.json")).unwrap();
+    assert_eq!(keys.len(), 3);
+    let validator = JwtValidator::default().with_keys(keys);
+
+    let shared = EncodingKey::from_secret(b"jwk-shared-secret");
+    assert!(validator.validate(&sign(Algorithm::HS256, Some("hs256"), &shared, &claims())).is_ok());
+    assert!(validator.validate(&rs256("rs256.key", Some("rs256"), &claims())).is_ok());
+    assert!(validator.validate(&es256(Some("es256"), &claims())).is_ok());
+}
+
+#[test]
+fn kids_only_select_the_key_they_name() {
+    let validator = JwtValidator::new(JwtKey::rs256_pem(fixture("rs256.pub.pem")).unwrap().with_key_id("current"));
+
+    // Tokens without a kid may be signed by any key for their algorithm
+    assert!(validator.validate(&rs256("rs256.key", None, &claims())).is_ok());
+    assert!(validator.validate(&rs256("rs256.key", Some("current"), &claims())).is_ok());
+    assert_eq!(rejection(validator.validate(&rs256("rs256.key", Some("previous"), &claims()))), "No key accepts RS256 tokens");
+}
+
+/// Records the `Authorization` of the request that it's sent, and answers with a 200.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Option<Option<Authorization>>>>);
+
+impl Capture {
+    fn authorization(&self) -> Option<Option<Authorization>> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl<C: Has<Option<Authorization>>> Service<(Request<Body>, C)> for Capture {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, (_request, context): (Request<Body>, C)) -> Self::Future {
+        *self.0.lock().unwrap() = Some(Has::<Option<Authorization>>::get(&context).clone());
+        future::ok(Response::new(Body::empty()))
+    }
+}
+
+/// Send a request with `token` through an `Authenticator` that accepts HS256 tokens.
+async fn authenticate(token: String, capture: &Capture) -> Response<Body> {
+    let validator = JwtValidator::new(JwtKey::hs256(SECRET)).with_audience("petstore");
+    let mut service = AuthenticatorLayer::new(InMemoryKeyStore::new())
+        .with_bearer(validator)
+        .layer(capture.clone());
+    let auth_data = Some(AuthData::Bearer(Bearer { token }));
+    let context: ServerContext = swagger::make_context!(ContextBuilder, EmptyContext, auth_data, None::<Authorization>, XSpanIdString::default());
+
+    service.call((Request::new(Body::empty()), context)).await.unwrap()
+}
+
+#[tokio::test]
+async fn accepted_tokens_authorize_the_request() {
+    let capture = Capture::default();
+
+    let response = authenticate(hs256(&claims()), &capture).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let authorization = capture.authorization().unwrap().unwrap();
+    assert_eq!(authorization.subject, "cosmo");
+    assert_eq!(authorization.scopes, scopes(&["read:pets", "write:pets"]));
+}
+
+#[tokio::test]
+async fn refused_tokens_are_challenged() {
+    let capture = Capture::default();
+    let mut claims = claims();
+    claims["aud"] = json!("bookstore");
+
+    let response = authenticate(hs256(&claims), &capture).await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer error=\"invalid_token\", error_description=\"Token has the wrong audience\"");
+    assert!(capture.authorization().is_none(), "Refused tokens shouldn't reach the service");
+}
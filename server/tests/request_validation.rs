@@ -0,0 +1,74 @@
+//! `ValidateRequestsLayer` checks requests against the API definition before they reach
+//! the service that it wraps.
+
+#![cfg(feature = "validation")]
+
+mod common;
+
+use common::{unauthenticated, ServerContext};
+use futures::future;
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::service::Service;
+use hyper::{Body, Request, Response, StatusCode};
+use openapi_client::server::body::BodyLimits;
+use openapi_client::server::validation::ValidateRequestsLayer;
+use openapi_client::spec::Spec;
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+use tower::Layer;
+
+/// A service that counts the requests that reach it.
+#[derive(Clone, Default)]
+struct Count(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+impl Service<(Request<Body>, ServerContext)> for Count {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: (Request<Body>, ServerContext)) -> Self::Future {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        future::ok(Response::new(Body::empty()))
+    }
+}
+
+fn spec() -> Spec {
+    Spec::from_file("api/openapi.yaml").expect("Unable to load the API definition")
+}
+
+async fn post_pet(layer: ValidateRequestsLayer, body: &str, content_length: bool) -> (StatusCode, usize) {
+    let count = Count::default();
+    let mut request = Request::post("/api/v3/pet")
+        .header(CONTENT_TYPE, "application/json");
+    if content_length {
+        request = request.header(CONTENT_LENGTH, body.len());
+    }
+    let request = request.body(Body::from(body.to_string())).unwrap();
+
+    let response = layer.layer(count.clone()).call((request, unauthenticated())).await.unwrap();
+    (response.status(), count.0.load(std::sync::atomic::Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn request_bodies_within_the_limit_are_checked() {
+    let pet = r#"{"name":"doggie","photoUrls":["https://example.com/doggie.png"]}"#;
+    let layer = ValidateRequestsLayer::new(spec()).with_body_limits(BodyLimits::new(16)
+        .operation("AddPet", Some(1024)));
+
+    assert_eq!(post_pet(layer.clone(), pet, true).await, (StatusCode::OK, 1));
+    assert_eq!(post_pet(layer, r#"{"name":"doggie"}"#, true).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn request_bodies_over_the_limit_are_rejected() {
+    let pet = r#"{"name":"doggie","photoUrls":["https://example.com/doggie.png"]}"#;
+    let layer = ValidateRequestsLayer::new(spec()).with_body_limits(BodyLimits::new(16));
+
+    // Before the body is read, given its length, and as soon as the limit is passed otherwise
+    assert_eq!(post_pet(layer.clone(), pet, true).await, (StatusCode::PAYLOAD_TOO_LARGE, 0));
+    assert_eq!(post_pet(layer, pet, false).await, (StatusCode::PAYLOAD_TOO_LARGE, 0));
+}
@@ -0,0 +1,83 @@
+//! The server dispatches every operation in the route table.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::{authorized, with_scopes, Recorder, ServerContext};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::Service as _;
+use hyper::{Body, Request, StatusCode};
+use openapi_client::server::router::ROUTES;
+use openapi_client::server::{ApiRequestParser, MakeService, Service};
+use swagger::RequestParser;
+use openapi_client::BASE_PATH;
+use swagger::auth::Scopes;
+
+/// A request path for `template`, with a value for each of its parameters.
+fn path(template: &str) -> String {
+    let path = template
+        .replace("{petId}", "1")
+        .replace("{orderId}", "1")
+        .replace("{username}", "alice");
+    format!("{}{}", BASE_PATH, path)
+}
+
+/// A body that the operation accepts.
+fn body(operation_id: &str) -> Body {
+    match operation_id {
+        "AddPet" | "UpdatePet" => Body::from(r#"{"name":"doggie","photoUrls":["https://example.com/doggie.jpg"]}"#),
+        "CreateUsersWithListInput" => Body::from("[]"),
+        _ => Body::empty(),
+    }
+}
+
+#[tokio::test]
+async fn every_route_is_dispatched() {
+    for route in ROUTES {
+        let api = Recorder::default();
+        let request = Request::builder()
+            .method(route.method.clone())
+            .uri(path(route.path))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body(route.operation_id))
+            .unwrap();
+
+        let response = Service::new(api.clone())
+            .call((request, authorized("alice")))
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(api.calls(), vec![route.operation_id], "{} {}: {:?}", route.method, route.path, body);
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{}", route.operation_id);
+    }
+}
+
+#[tokio::test]
+async fn route_scopes_are_enforced() {
+    let context = with_scopes("alice", Scopes::Some(vec!["read:pets".to_string()].into_iter().collect()));
+    let api = Recorder::default();
+    let request = Request::get(path("/pet/{petId}")).body(Body::empty()).unwrap();
+
+    let response = Service::new(api.clone()).call((request, context)).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(api.calls().is_empty());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("missing scopes write:pets"), "{}", body);
+}
+
+#[test]
+fn operation_ids_follow_the_base_path() {
+    let request = Request::get("/petstore/v3/pet/1").body(()).unwrap();
+    let service = Service::<_, ServerContext>::new(Recorder::default());
+    let make_service = MakeService::<_, ServerContext>::new(Recorder::default());
+
+    assert_eq!(service.operation_id(&request), None);
+    assert_eq!(service.with_base_path("/petstore/v3").operation_id(&request), Some("GetPetById"));
+    assert_eq!(make_service.with_base_path("/petstore/v3").operation_id(&request), Some("GetPetById"));
+    assert_eq!(ApiRequestParser::parse_operation_id(&Request::get("/api/v3/pet/1").body(()).unwrap()), Some("GetPetById"));
+}
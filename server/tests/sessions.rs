@@ -0,0 +1,176 @@
+//! `Sessions` issues, expires and revokes session tokens, and `FileSessionStore` keeps
+//! them across restarts.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{authorized, unauthenticated, Recorder};
+use hyper::service::Service as _;
+use hyper::{Body, Request, StatusCode};
+use openapi_client::server::session::{Session, SessionError};
+use openapi_client::server::{FileSessionStore, InMemorySessionStore, KeyStore, Service, SessionStore, Sessions, TokenValidator};
+use openapi_client::BASE_PATH;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use swagger::auth::{AuthData, Scopes};
+
+/// A path for a session file that doesn't exist yet, and is removed when dropped.
+struct SessionFile(PathBuf);
+
+impl SessionFile {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("openapi-client-{}-{}.json", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        SessionFile(path)
+    }
+}
+
+impl Drop for SessionFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn scopes(scopes: &[&str]) -> BTreeSet<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
+
+#[test]
+fn login_issues_a_new_token_for_each_session() {
+    let sessions = Sessions::new(InMemorySessionStore::new());
+
+    let first = sessions.login("alice", vec!["read:pets"]).unwrap();
+    let second = sessions.login("alice", vec!["read:pets"]).unwrap();
+
+    assert_eq!(first.token.len(), 64);
+    assert!(first.token.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(first.token, second.token);
+    assert_eq!(first.subject, "alice");
+    assert_eq!(first.scopes, scopes(&["read:pets"]));
+    let lifetime = first.expires_at - Utc::now();
+    assert!(lifetime > Duration::minutes(59) && lifetime <= Duration::hours(1), "{}", lifetime);
+}
+
+#[test]
+fn tokens_are_accepted_as_api_keys_and_bearer_tokens() {
+    let sessions = Sessions::new(InMemorySessionStore::new());
+    let session = sessions.login("alice", vec!["read:pets", "write:pets"]).unwrap();
+
+    let authorization = sessions.authorize(&session.token).unwrap();
+    assert_eq!(authorization.subject, "alice");
+    assert_eq!(authorization.scopes, Scopes::Some(scopes(&["read:pets", "write:pets"])));
+    assert_eq!(sessions.validate(&session.token).unwrap(), authorization);
+
+    assert!(sessions.authorize("not-a-session").is_none());
+    assert_eq!(sessions.validate("not-a-session").unwrap_err().0, "Session is unknown or has expired");
+}
+
+#[test]
+fn expired_sessions_are_refused_and_cleared_out() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let expired = Sessions::new(store.clone()).with_lifetime(Duration::seconds(-1));
+    let sessions = Sessions::new(store.clone());
+
+    let session = expired.login("alice", Vec::<String>::new()).unwrap();
+    assert!(session.is_expired(Utc::now()));
+    assert!(store.get(&session.token).unwrap().is_some());
+    assert!(sessions.session(&session.token).unwrap().is_none());
+    assert!(sessions.authorize(&session.token).is_none());
+    assert!(sessions.validate(&session.token).is_err());
+
+    // The next login clears it out of the store
+    sessions.login("bob", Vec::<String>::new()).unwrap();
+    assert!(store.get(&session.token).unwrap().is_none());
+}
+
+#[test]
+fn logout_revokes_the_token_that_the_request_carried() {
+    let sessions = Sessions::new(InMemorySessionStore::new());
+    let by_key = sessions.login("alice", Vec::<String>::new()).unwrap();
+    let by_bearer = sessions.login("alice", Vec::<String>::new()).unwrap();
+
+    let revoked = sessions.logout(&Some(AuthData::ApiKey(by_key.token.clone()))).unwrap();
+    assert_eq!(revoked.as_ref(), Some(&by_key));
+    assert!(sessions.authorize(&by_key.token).is_none());
+    assert!(sessions.authorize(&by_bearer.token).is_some());
+
+    let revoked = sessions.logout(&Some(AuthData::bearer(&by_bearer.token))).unwrap();
+    assert_eq!(revoked.as_ref(), Some(&by_bearer));
+    assert!(sessions.validate(&by_bearer.token).is_err());
+
+    // Logging out again, or without a token, is harmless
+    assert!(sessions.logout(&Some(AuthData::bearer(&by_bearer.token))).unwrap().is_none());
+    assert!(sessions.logout(&None).unwrap().is_none());
+}
+
+#[test]
+fn file_sessions_survive_a_restart() {
+    let file = SessionFile::new("restart");
+    let (kept, revoked) = {
+        let sessions = Sessions::new(FileSessionStore::open(&file.0).unwrap());
+        let kept = sessions.login("alice", vec!["read:pets"]).unwrap();
+        let revoked = sessions.login("bob", vec!["read:pets"]).unwrap();
+        sessions.logout(&Some(AuthData::ApiKey(revoked.token.clone()))).unwrap();
+        (kept, revoked)
+    };
+
+    let sessions = Sessions::new(FileSessionStore::open(&file.0).unwrap());
+
+    assert_eq!(sessions.session(&kept.token).unwrap(), Some(kept));
+    assert!(sessions.session(&revoked.token).unwrap().is_none());
+}
+
+#[test]
+fn file_sessions_drop_expired_sessions_from_the_file() {
+    let file = SessionFile::new("expiry");
+    let store = FileSessionStore::open(&file.0).unwrap();
+    store.insert(Session {
+        token: "expired".to_string(),
+        subject: "alice".to_string(),
+        scopes: BTreeSet::new(),
+        expires_at: Utc::now() - Duration::seconds(1),
+    }).unwrap();
+    assert!(FileSessionStore::open(&file.0).unwrap().get("expired").unwrap().is_some());
+
+    store.remove_expired(Utc::now()).unwrap();
+
+    assert!(FileSessionStore::open(&file.0).unwrap().get("expired").unwrap().is_none());
+}
+
+#[test]
+fn missing_session_files_start_empty() {
+    let file = SessionFile::new("missing");
+
+    let store = FileSessionStore::open(&file.0).unwrap();
+
+    assert!(store.get("anything").unwrap().is_none());
+    assert!(!file.0.exists(), "Opening the store shouldn't create the file");
+}
+
+#[test]
+fn unreadable_session_files_are_refused() {
+    let file = SessionFile::new("corrupt");
+    fs::write(&file.0, "not json").unwrap();
+
+    match FileSessionStore::open(&file.0) {
+        Err(SessionError::Json(_)) => {},
+        other => panic!("Expected a JSON error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn logout_needs_credentials() {
+    let logout = || Request::get(format!("{}/user/logout", BASE_PATH)).body(Body::empty()).unwrap();
+
+    let api = Recorder::default();
+    let response = Service::new(api.clone()).call((logout(), unauthenticated())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(api.calls().is_empty());
+
+    Service::new(api.clone()).call((logout(), authorized("alice"))).await.unwrap();
+    assert_eq!(api.calls(), vec!["LogoutUser"]);
+}
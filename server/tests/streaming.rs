@@ -0,0 +1,66 @@
+//! Users posted to `createWithList` are streamed to the implementation.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::{unauthenticated, Recorder};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::Service as _;
+use hyper::{Body, Request, Response, StatusCode};
+use openapi_client::server::body::BodyLimits;
+use openapi_client::server::Service;
+
+const USERS: &str = r#"[{"username":"alice"},{"username":42},{"username":"carol"}]"#;
+
+async fn create_users(mut service: Service<Recorder, common::ServerContext>, body: &'static str) -> Response<Body> {
+    let request = Request::post("/api/v3/user/createWithList")
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    service.call((request, unauthenticated())).await.unwrap()
+}
+
+#[tokio::test]
+async fn body_errors_are_reported_when_the_implementation_fails() {
+    let api = Recorder::default();
+
+    let response = create_users(Service::new(api.clone()), USERS).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("Element 1 of the body is invalid"), "{}", body);
+    assert!(api.calls().is_empty());
+}
+
+#[tokio::test]
+async fn responses_of_implementations_that_handle_body_errors_stand() {
+    let api = Recorder::accepting_partial_lists();
+
+    let response = create_users(Service::new(api.clone()), USERS).await;
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(api.calls(), vec!["CreateUsersWithListInput"]);
+}
+
+#[tokio::test]
+async fn streamed_bodies_are_limited() {
+    let api = Recorder::default();
+    let service = Service::new(api.clone()).with_body_limits(BodyLimits::new(32));
+
+    let response = create_users(service, USERS).await;
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(api.calls().is_empty());
+}
+
+#[tokio::test]
+async fn valid_bodies_are_passed_on() {
+    let api = Recorder::default();
+
+    let response = create_users(Service::new(api.clone()), r#"[{"username":"alice"}]"#).await;
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(api.calls(), vec!["CreateUsersWithListInput"]);
+}
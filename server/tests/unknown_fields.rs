@@ -0,0 +1,71 @@
+//! Unknown request body fields are handled as the operation's policy says.
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::{authorized, Recorder};
+use hyper::header::{CONTENT_TYPE, WARNING};
+use hyper::service::Service as _;
+use hyper::{Body, Request, Response, StatusCode};
+use openapi_client::server::body::BodyLimits;
+use openapi_client::server::unknown_fields::{UnknownFields, UnknownFieldsPolicy};
+use openapi_client::server::Service;
+
+/// A pet with `photoUrl` misspelt.
+const PET: &str = r#"{"name":"doggie","photoUrls":["https://example.com/doggie.png"],"photoUrl":"https://example.com/doggie.jpg"}"#;
+
+async fn add_pet(policy: Option<UnknownFieldsPolicy>) -> (Response<Body>, Recorder) {
+    let api = Recorder::default();
+    let mut service = Service::new(api.clone());
+    if let Some(policy) = policy {
+        service = service.with_unknown_fields(policy);
+    }
+    let request = Request::post("/api/v3/pet")
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(PET))
+        .unwrap();
+
+    (service.call((request, authorized("alice"))).await.unwrap(), api)
+}
+
+#[tokio::test]
+async fn unknown_fields_are_warned_about_by_default() {
+    let (response, api) = add_pet(None).await;
+
+    assert_eq!(api.calls(), vec!["AddPet"]);
+    let warning = response.headers().get(WARNING).expect("No Warning header").to_str().unwrap();
+    assert!(warning.contains("/photoUrl"), "{}", warning);
+}
+
+#[tokio::test]
+async fn unknown_fields_can_be_ignored() {
+    let (response, api) = add_pet(Some(UnknownFieldsPolicy::new(UnknownFields::Ignore))).await;
+
+    assert_eq!(api.calls(), vec!["AddPet"]);
+    assert!(response.headers().get(WARNING).is_none());
+}
+
+#[tokio::test]
+async fn unknown_fields_can_be_rejected_per_operation() {
+    let policy = UnknownFieldsPolicy::new(UnknownFields::Ignore).operation("AddPet", UnknownFields::Reject);
+    let (response, api) = add_pet(Some(policy)).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(api.calls().is_empty());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("unknown-fields") && body.contains("/photoUrl"), "{}", body);
+}
+
+#[test]
+#[should_panic(expected = "No operation has the ID AddPett")]
+fn policies_for_unknown_operations_are_refused() {
+    let _ = UnknownFieldsPolicy::default().operation("AddPett", UnknownFields::Reject);
+}
+
+#[test]
+#[should_panic(expected = "No operation has the ID AddPett")]
+fn body_limits_for_unknown_operations_are_refused() {
+    let _ = BodyLimits::default().operation("AddPett", None);
+}
@@ -0,0 +1,93 @@
+//! `CheckResponses` checks responses against the API definition.
+
+#![cfg(feature = "validation")]
+
+use futures::future;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::Service;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use openapi_client::server::validation::CheckResponses;
+use openapi_client::spec::Spec;
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+
+/// Answers a request with the response that it's given.
+struct Respond(Option<Response<Body>>);
+
+impl Service<(Request<Body>, ())> for Respond {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: (Request<Body>, ())) -> Self::Future {
+        future::ok(self.0.take().expect("Only one request is sent"))
+    }
+}
+
+/// Send a request to a service that answers it with `response`, checking the response.
+async fn check(method: Method, path: &str, response: Response<Body>) -> Response<Body> {
+    let inner = Respond(Some(response));
+    let request = Request::builder().method(method).uri(path).body(Body::empty()).unwrap();
+
+    CheckResponses::new(inner, spec()).call((request, ())).await.unwrap()
+}
+
+fn json(status: StatusCode, body: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn spec() -> Spec {
+    Spec::from_file("api/openapi.yaml").expect("Unable to load the API definition")
+}
+
+#[tokio::test]
+async fn conforming_responses_pass() {
+    let response = check(Method::GET, "/api/v3/pet/1", json(StatusCode::OK, r#"{"name":"doggie","photoUrls":["https://example.com/doggie.png"]}"#)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], br#"{"name":"doggie","photoUrls":["https://example.com/doggie.png"]}"#);
+}
+
+#[tokio::test]
+#[should_panic(expected = "violates the API definition")]
+async fn undeclared_status_codes_are_violations() {
+    check(Method::GET, "/api/v3/pet/1", json(StatusCode::CREATED, "{}")).await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "HEAD /api/v3/pet/1 returned a 201 response")]
+async fn head_responses_are_checked_against_the_get_operation() {
+    check(Method::HEAD, "/api/v3/pet/1", json(StatusCode::CREATED, "")).await;
+}
+
+#[tokio::test]
+async fn head_responses_have_no_body_to_check() {
+    let response = check(Method::HEAD, "/api/v3/pet/1", json(StatusCode::OK, "")).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn optional_headers_may_be_missing() {
+    let response = check(Method::GET, "/api/v3/user/login", json(StatusCode::OK, r#""token""#)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+#[should_panic(expected = "/header/X-Rate-Limit")]
+async fn headers_are_checked_against_their_schema() {
+    let mut response = json(StatusCode::OK, r#""token""#);
+    response.headers_mut().insert("x-rate-limit", "lots".parse().unwrap());
+
+    check(Method::GET, "/api/v3/user/login", response).await;
+}